@@ -1,17 +1,141 @@
+use crate::graph::{self, GraphLine};
 use git2::{Cred, FetchOptions, RemoteCallbacks, Repository};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+#[derive(Debug)]
+pub enum GitError {
+    NotARepository,
+    BranchNotFound(String),
+    // Merge, cherry-pick and revert report conflicts in their results; this is
+    // for operations like pull that have no result to put them in
+    MergeConflict(Vec<String>),
+    PushRejected(Vec<String>),
+    AuthFailed,
+    NetworkError(String),
+    PermissionDenied(String),
+    Unknown(String),
+}
+
+impl GitError {
+    fn code(&self) -> &'static str {
+        match self {
+            GitError::NotARepository => "not_a_repository",
+            GitError::BranchNotFound(_) => "branch_not_found",
+            GitError::MergeConflict(_) => "merge_conflict",
+            GitError::PushRejected(_) => "push_rejected",
+            GitError::AuthFailed => "auth_failed",
+            GitError::NetworkError(_) => "network_error",
+            GitError::PermissionDenied(_) => "permission_denied",
+            GitError::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for GitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitError::NotARepository => write!(f, "Not a git repository"),
+            GitError::BranchNotFound(name) => write!(f, "Branch '{}' not found", name),
+            GitError::MergeConflict(paths) if paths.is_empty() => write!(f, "Merge conflict"),
+            GitError::MergeConflict(paths) => write!(f, "Merge conflict in {}", paths.join(", ")),
+            GitError::PushRejected(refs) => write!(f, "Push rejected: {}", refs.join(", ")),
+            GitError::AuthFailed => write!(f, "Authentication failed"),
+            GitError::NetworkError(message)
+            | GitError::PermissionDenied(message)
+            | GitError::Unknown(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        match (e.code(), e.class()) {
+            (git2::ErrorCode::NotFound, git2::ErrorClass::Repository) => GitError::NotARepository,
+            (git2::ErrorCode::NotFound, git2::ErrorClass::Reference)
+                if e.message().contains("branch") || e.message().contains("refs/heads/") =>
+            {
+                // libgit2 quotes the name it failed to resolve
+                let name = e.message().split('\'').nth(1).unwrap_or(e.message());
+                GitError::BranchNotFound(name.trim_start_matches("refs/heads/").to_string())
+            }
+            (git2::ErrorCode::Conflict | git2::ErrorCode::MergeConflict, _) => {
+                GitError::MergeConflict(Vec::new())
+            }
+            (git2::ErrorCode::Auth, _) => GitError::AuthFailed,
+            (_, git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh) => {
+                GitError::NetworkError(e.message().to_string())
+            }
+            (_, git2::ErrorClass::Os) if e.message().contains("ermission denied") => {
+                GitError::PermissionDenied(e.message().to_string())
+            }
+            _ => GitError::Unknown(e.message().to_string()),
+        }
+    }
+}
+
+// Serialized as { code, message } so the frontend can match on the code and
+// still show the same text it did before
+impl Serialize for GitError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("GitError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
 #[derive(Serialize)]
 pub struct GitBranch {
     pub name: String,
     pub is_head: bool,
+    pub upstream: Option<String>,
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
 }
 
 #[derive(Serialize)]
 pub struct GitRemote {
     pub name: String,
     pub url: String,
+    pub push_url: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RemoteRef {
+    pub name: String,
+    pub oid: String,
+}
+
+#[derive(Serialize)]
+pub struct FetchResult {
+    pub path: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub target_oid: String,
+    pub is_annotated: bool,
+    pub tagger_name: Option<String>,
+    pub tagger_email: Option<String>,
+    pub tagger_date: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CloneProgress {
+    pub received_objects: usize,
+    pub total_objects: usize,
+    pub received_bytes: usize,
+    pub indexed_deltas: usize,
+    pub total_deltas: usize,
 }
 
 #[derive(Serialize)]
@@ -21,11 +145,107 @@ pub struct GitSubmodule {
     pub url: String,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmoduleStatus {
+    Clean,
+    Modified,
+    Uninitialized,
+    OutOfDate,
+    Conflict,
+}
+
+#[derive(Serialize)]
+pub struct GitSubmoduleStatus {
+    pub name: String,
+    pub path: String,
+    pub url: String,
+    pub head_oid: Option<String>,
+    pub workdir_oid: Option<String>,
+    pub status: SubmoduleStatus,
+}
+
+#[derive(Serialize)]
+pub struct GitWorktree {
+    pub path: String,
+    pub branch: String,
+    pub head: String,
+    pub is_main: bool,
+    pub is_locked: bool,
+}
+
+#[derive(Serialize)]
+pub struct RepositoryInfo {
+    pub name: String,
+    pub path: String,
+    pub description: Option<String>,
+    pub is_bare: bool,
+    pub is_shallow: bool,
+    pub is_empty: bool,
+    pub state: String,
+}
+
+#[derive(Serialize)]
+pub struct HeadInfo {
+    pub branch_name: Option<String>,
+    pub commit_id: String,
+    pub is_detached: bool,
+    pub state: String,
+}
+
+#[derive(Serialize)]
+pub struct RepoState {
+    pub state: String,
+    pub step: Option<usize>,
+    pub total: Option<usize>,
+}
+
+// Sizes are in KiB, as reported by git count-objects
+#[derive(Serialize, Default)]
+pub struct ObjectCount {
+    pub count: u64,
+    pub size: u64,
+    pub in_pack: u64,
+    pub packs: u64,
+    pub size_pack: u64,
+    pub prune_packable: u64,
+    pub garbage: u64,
+}
+
+#[derive(Serialize)]
+pub struct PackInfo {
+    pub name: String,
+    pub objects: u64,
+    pub size_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct FsckResult {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub ok: bool,
+}
+
+#[derive(Serialize)]
+pub struct RepoSize {
+    pub loose_objects: u64,
+    pub pack_files: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GitSignature {
+    pub name: String,
+    pub email: String,
+}
+
 #[derive(Serialize)]
 pub struct GitFileStatus {
     pub path: String,
+    pub old_path: Option<String>,
     pub status: String,
     pub staged: bool,
+    pub conflict_type: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -42,12 +262,32 @@ pub struct GitCommit {
     pub lines: Vec<GraphLine>,
 }
 
-#[derive(Serialize, Clone)]
-pub struct GraphLine {
-    pub upper: bool,  // true = upper half, false = lower half
-    pub from: usize,  // starting lane
-    pub to: usize,    // ending lane
-    pub color: usize, // color index
+#[derive(Serialize)]
+pub struct CommitPage {
+    pub commits: Vec<GitCommit>,
+    pub has_more: bool,
+}
+
+#[derive(Serialize)]
+pub struct GitCommitStat {
+    pub id: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub files_changed: usize,
+}
+
+#[derive(Serialize)]
+pub struct BranchComparison {
+    pub ahead: Vec<GitCommit>,
+    pub behind: Vec<GitCommit>,
+    pub merge_base: String,
+}
+
+#[derive(Serialize)]
+pub struct GraphPosition {
+    pub id: String,
+    pub lane: usize,
+    pub lines: Vec<GraphLine>,
 }
 
 #[derive(Serialize, Clone)]
@@ -65,6 +305,9 @@ pub struct CommitFile {
     pub additions: usize,
     pub deletions: usize,
     pub lines: Vec<DiffLine>,
+    pub is_binary: bool,
+    pub binary_size_old: Option<u64>,
+    pub binary_size_new: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -75,872 +318,4894 @@ pub struct DiffLine {
     pub content: String,
 }
 
-fn create_remote_callbacks<'a>() -> RemoteCallbacks<'a> {
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-        Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
-    });
-    callbacks.transfer_progress(|stats| {
-        println!(
-            "Received {}/{} objects ({} bytes)",
-            stats.received_objects(),
-            stats.total_objects(),
-            stats.received_bytes()
-        );
-        true
-    });
-    callbacks
+#[derive(Serialize)]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
 }
 
-pub fn clone_repository(url: &str, path: &str) -> Result<(), git2::Error> {
-    let callbacks = create_remote_callbacks();
+#[derive(Serialize)]
+pub struct DiffStat {
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub status: String,
+}
 
-    let mut fetch_options = FetchOptions::new();
-    fetch_options.remote_callbacks(callbacks);
+#[derive(Serialize)]
+pub struct IndexEntry {
+    pub path: String,
+    pub oid: String,
+    pub mode: u32,
+    pub size: u64,
+}
 
-    let mut builder = git2::build::RepoBuilder::new();
-    builder.fetch_options(fetch_options);
-    builder.clone(url, Path::new(path))?;
-    Ok(())
+#[derive(Deserialize, Clone, Copy)]
+pub struct DiffViewOptions {
+    pub word_diff: bool,
+    pub ignore_whitespace: bool,
+    pub context_lines: u32,
 }
 
-pub fn is_git_repository(path: &str) -> bool {
-    Path::new(path).join(".git").exists()
+#[derive(Serialize)]
+pub struct WordChange {
+    pub kind: char,
+    pub text: String,
 }
 
-pub fn open_repository(path: &str) -> Result<Repository, git2::Error> {
-    Repository::open(path)
+#[derive(Serialize)]
+pub struct DiffHunkView {
+    pub raw: String,
+    // One entry per context/added/removed line of `raw`, in order; empty unless
+    // word diffs were requested
+    pub word_lines: Vec<Vec<WordChange>>,
 }
 
-pub fn get_branches(repo: &Repository) -> Result<Vec<GitBranch>, git2::Error> {
-    let mut branches = Vec::new();
-    let is_detached = repo.head_detached()?;
-    let head = repo.head()?;
-    let head_name = head.shorthand();
+#[derive(Serialize)]
+pub struct RebaseStatus {
+    pub completed: bool,
+    pub conflicts: Vec<String>,
+    pub current_step: usize,
+    pub total_steps: usize,
+}
 
-    // Add detached HEAD as a special branch if applicable
-    if is_detached {
-        branches.push(GitBranch {
-            name: "HEAD (detached)".to_string(),
-            is_head: true,
-        });
-    }
+#[derive(Serialize)]
+pub struct CherryPickResult {
+    pub conflicts: Vec<String>,
+    pub needs_commit: bool,
+}
 
-    for branch in repo.branches(Some(git2::BranchType::Local))? {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            branches.push(GitBranch {
-                name: name.to_string(),
-                is_head: !is_detached && Some(name) == head_name,
-            });
-        }
-    }
+#[derive(Serialize)]
+pub struct CherryPickRangeResult {
+    pub picked: Vec<String>,
+    pub conflicts_at: Option<String>,
+}
 
-    Ok(branches)
+#[derive(Serialize)]
+pub struct RevertResult {
+    pub conflicts: Vec<String>,
+    pub reverted_commit: Option<String>,
 }
 
-pub fn get_branch_head(repo: &Repository, branch_name: &str) -> Result<String, git2::Error> {
-    // Try local branch first
-    if let Ok(branch) = repo.find_branch(branch_name, git2::BranchType::Local) {
-        let commit = branch.get().peel_to_commit()?;
-        return Ok(commit.id().to_string());
-    }
+#[derive(Serialize)]
+pub struct ApplyResult {
+    pub applied: bool,
+    pub conflicts: Vec<String>,
+}
 
-    // Try remote branch
-    if let Ok(branch) = repo.find_branch(branch_name, git2::BranchType::Remote) {
-        let commit = branch.get().peel_to_commit()?;
-        return Ok(commit.id().to_string());
-    }
+#[derive(Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line_no: u32,
+    pub content: String,
+}
 
-    // Try as a reference (for remote branches like origin/branch)
-    let ref_name = format!("refs/remotes/{}", branch_name);
-    if let Ok(reference) = repo.find_reference(&ref_name) {
-        let commit = reference.peel_to_commit()?;
-        return Ok(commit.id().to_string());
-    }
+#[derive(Serialize)]
+pub struct GrepResult {
+    pub matches: Vec<GrepMatch>,
+    pub truncated: bool,
+}
 
-    Err(git2::Error::from_str(&format!(
-        "Branch '{}' not found",
-        branch_name
-    )))
+#[derive(Serialize)]
+pub struct GitStash {
+    pub index: usize,
+    pub message: String,
+    pub id: String,
 }
 
-pub fn get_remotes(repo: &Repository) -> Result<Vec<GitRemote>, git2::Error> {
-    let mut remotes = Vec::new();
+#[derive(Serialize)]
+pub struct ReflogEntry {
+    pub id_old: String,
+    pub id_new: String,
+    pub message: String,
+    pub author: String,
+    pub timestamp: String,
+}
 
-    for name in repo.remotes()?.iter().flatten() {
-        if let Ok(remote) = repo.find_remote(name) {
-            if let Some(url) = remote.url() {
-                remotes.push(GitRemote {
-                    name: name.to_string(),
-                    url: url.to_string(),
-                });
-            }
+#[derive(Deserialize, Default)]
+pub struct CloneOptions {
+    pub depth: Option<u32>,
+    #[serde(default)]
+    pub single_branch: bool,
+    pub branch: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub enum AuthConfig {
+    #[default]
+    SshAgent,
+    SshKey {
+        path: String,
+        passphrase: Option<String>,
+    },
+    HttpsToken(String),
+    HttpsPassword {
+        username: String,
+        password: String,
+    },
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ResetMode {
+    Soft,
+    Mixed,
+    Hard,
+}
+
+impl std::str::FromStr for ResetMode {
+    type Err = GitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "soft" => Ok(ResetMode::Soft),
+            "mixed" => Ok(ResetMode::Mixed),
+            "hard" => Ok(ResetMode::Hard),
+            _ => Err(GitError::Unknown(format!("Invalid reset mode: {}", s))),
         }
     }
+}
 
-    Ok(remotes)
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchIn {
+    Message,
+    Author,
+    Email,
+    Diff,
 }
 
-pub fn get_remote_branches(
-    repo: &Repository,
-    remote_name: &str,
-) -> Result<Vec<String>, git2::Error> {
-    let mut branches = Vec::new();
-    let prefix = format!("{}/", remote_name);
+impl std::str::FromStr for SearchIn {
+    type Err = GitError;
 
-    for branch in repo.branches(Some(git2::BranchType::Remote))? {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            if name.starts_with(&prefix) {
-                let branch_name = name.strip_prefix(&prefix).unwrap_or(name);
-                if branch_name != "HEAD" {
-                    branches.push(branch_name.to_string());
-                }
-            }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "message" => Ok(SearchIn::Message),
+            "author" => Ok(SearchIn::Author),
+            "email" => Ok(SearchIn::Email),
+            "diff" => Ok(SearchIn::Diff),
+            _ => Err(GitError::Unknown(format!("Invalid search field: {}", s))),
         }
     }
+}
 
-    Ok(branches)
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
 }
 
-pub fn get_tags(repo: &Repository) -> Result<Vec<String>, git2::Error> {
-    let mut tags = Vec::new();
+impl std::str::FromStr for ArchiveFormat {
+    type Err = GitError;
 
-    repo.tag_foreach(|_oid, name| {
-        if let Ok(name_str) = std::str::from_utf8(name) {
-            if let Some(tag_name) = name_str.strip_prefix("refs/tags/") {
-                tags.push(tag_name.to_string());
-            }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "zip" => Ok(ArchiveFormat::Zip),
+            "targz" | "tar.gz" | "tgz" => Ok(ArchiveFormat::TarGz),
+            _ => Err(GitError::Unknown(format!("Invalid archive format: {}", s))),
         }
-        true
-    })?;
-
-    tags.sort();
-    Ok(tags)
+    }
 }
 
-pub fn get_tag_commit(repo: &Repository, tag_name: &str) -> Result<String, git2::Error> {
-    let reference = repo.find_reference(&format!("refs/tags/{}", tag_name))?;
-    let target = reference.peel_to_commit()?;
-    Ok(target.id().to_string())
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLevel {
+    Local,
+    Global,
+    System,
 }
 
-pub fn get_submodules(repo: &Repository) -> Result<Vec<GitSubmodule>, git2::Error> {
-    let mut submodules = Vec::new();
+impl std::str::FromStr for ConfigLevel {
+    type Err = GitError;
 
-    for submodule in repo.submodules()? {
-        if let (Some(name), Some(url)) = (submodule.name(), submodule.url()) {
-            let path = submodule.path();
-            submodules.push(GitSubmodule {
-                name: name.to_string(),
-                path: path.to_str().unwrap_or("").to_string(),
-                url: url.to_string(),
-            });
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "local" => Ok(ConfigLevel::Local),
+            "global" => Ok(ConfigLevel::Global),
+            "system" => Ok(ConfigLevel::System),
+            _ => Err(GitError::Unknown(format!("Invalid config level: {}", s))),
         }
     }
+}
 
-    Ok(submodules)
+impl From<ConfigLevel> for git2::ConfigLevel {
+    fn from(level: ConfigLevel) -> Self {
+        match level {
+            ConfigLevel::Local => git2::ConfigLevel::Local,
+            ConfigLevel::Global => git2::ConfigLevel::Global,
+            ConfigLevel::System => git2::ConfigLevel::System,
+        }
+    }
 }
 
-pub fn get_status(repo: &Repository) -> Result<Vec<GitFileStatus>, git2::Error> {
-    let mut files = Vec::new();
-    let statuses = repo.statuses(None)?;
+#[derive(Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: String,
+    pub level: String,
+}
 
-    for entry in statuses.iter() {
-        if let Some(path) = entry.path() {
-            let status = entry.status();
+#[derive(Serialize)]
+pub struct GitAttribute {
+    pub pattern: String,
+    pub key: String,
+    pub value: String,
+}
 
-            // Check if file is staged (in index)
-            if status.is_index_new()
-                || status.is_index_modified()
-                || status.is_index_deleted()
-                || status.is_index_renamed()
-            {
-                files.push(GitFileStatus {
-                    path: path.to_string(),
-                    status: format_status(status),
-                    staged: true,
-                });
-            }
+#[derive(Serialize)]
+pub struct GitBlameLine {
+    pub line_no: u32,
+    pub commit_id: String,
+    pub author: String,
+    pub email: String,
+    pub timestamp: String,
+    pub content: String,
+}
 
-            // Check if file is unstaged (in working tree)
-            if status.is_wt_new()
-                || status.is_wt_modified()
-                || status.is_wt_deleted()
-                || status.is_wt_renamed()
-            {
-                files.push(GitFileStatus {
-                    path: path.to_string(),
-                    status: format_status(status),
-                    staged: false,
-                });
-            }
-        }
-    }
+#[derive(Serialize)]
+pub struct TreeEntry {
+    pub name: String,
+    pub path: String,
+    pub kind: String,
+    pub oid: String,
+    pub size: Option<u64>,
+}
 
-    Ok(files)
+#[derive(Serialize)]
+pub struct MergeResult {
+    pub fast_forwarded: bool,
+    pub conflicts: Vec<String>,
+    pub merged_commit: Option<String>,
 }
 
-fn format_status(status: git2::Status) -> String {
-    if status.is_index_new() || status.is_wt_new() {
-        "added".to_string()
-    } else if status.is_index_modified() || status.is_wt_modified() {
-        "modified".to_string()
-    } else if status.is_index_deleted() || status.is_wt_deleted() {
-        "deleted".to_string()
-    } else if status.is_index_renamed() || status.is_wt_renamed() {
-        "renamed".to_string()
-    } else {
-        "unknown".to_string()
-    }
+#[derive(Serialize)]
+pub struct ConflictEntry {
+    pub path: String,
+    pub our_oid: Option<String>,
+    pub their_oid: Option<String>,
 }
 
-pub fn get_diff(repo: &Repository, path: &str, staged: bool) -> Result<String, git2::Error> {
-    // Check if file is untracked
-    let statuses = repo.statuses(None)?;
-    let mut is_untracked = false;
+#[derive(Serialize)]
+pub struct ConflictVersions {
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
 
-    for entry in statuses.iter() {
-        if entry.path() == Some(path) && entry.status().is_wt_new() {
-            is_untracked = true;
-            break;
-        }
+const MAX_CREDENTIAL_ATTEMPTS: usize = 3;
+
+struct SshKeySettings {
+    key_path: String,
+    passphrase: Option<String>,
+}
+
+// Commands run on arbitrary worker threads, so this has to be process-wide
+static SSH_KEY_SETTINGS: std::sync::Mutex<Option<SshKeySettings>> = std::sync::Mutex::new(None);
+
+pub fn set_ssh_key_path(path: &str, passphrase: Option<&str>) -> Result<(), GitError> {
+    if !Path::new(path).is_file() {
+        return Err(GitError::Unknown(format!("SSH key {} not found", path)));
     }
 
-    // For untracked files, generate diff manually
-    if is_untracked && !staged {
-        let workdir = repo
-            .workdir()
-            .ok_or_else(|| git2::Error::from_str("Repository has no working directory"))?;
-        let file_path = workdir.join(path);
+    let mut settings = SSH_KEY_SETTINGS
+        .lock()
+        .map_err(|_| GitError::Unknown("SSH key settings are unavailable".to_string()))?;
+    *settings = Some(SshKeySettings {
+        key_path: path.to_string(),
+        passphrase: passphrase.map(|p| p.to_string()),
+    });
+    Ok(())
+}
 
-        if let Ok(content) = std::fs::read_to_string(&file_path) {
-            let line_count = content.lines().count();
-            let mut diff_text = format!("@@ -0,0 +1,{} @@\n", line_count);
-            for line in content.lines() {
-                diff_text.push('+');
-                diff_text.push_str(line);
-                diff_text.push('\n');
-            }
-            return Ok(diff_text);
+// Explicitly configured key first, then the agent, then a default key in ~/.ssh
+fn ssh_credential(username: &str, attempt: usize) -> Result<Cred, git2::Error> {
+    if let Ok(settings) = SSH_KEY_SETTINGS.lock() {
+        if let Some(settings) = settings.as_ref() {
+            return Cred::ssh_key(
+                username,
+                None,
+                Path::new(&settings.key_path),
+                settings.passphrase.as_deref(),
+            );
         }
     }
 
-    let mut diff_options = git2::DiffOptions::new();
-    diff_options.pathspec(path);
+    let agent_available = std::env::var_os("SSH_AUTH_SOCK").is_some();
+    if attempt == 1 && agent_available {
+        return Cred::ssh_key_from_agent(username);
+    }
 
-    let diff = if staged {
-        let head = repo.head()?.peel_to_tree()?;
-        repo.diff_tree_to_index(Some(&head), None, Some(&mut diff_options))?
-    } else {
-        repo.diff_index_to_workdir(None, Some(&mut diff_options))?
-    };
+    match default_ssh_key() {
+        Some(key_path) => Cred::ssh_key(username, None, &key_path, None),
+        None => Cred::ssh_key_from_agent(username),
+    }
+}
 
-    let mut diff_text = String::new();
-    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
-        let origin = line.origin();
-        let content = std::str::from_utf8(line.content()).unwrap_or("");
+fn default_ssh_key() -> Option<std::path::PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    let ssh_dir = Path::new(&home).join(".ssh");
 
-        match origin {
-            '+' | '-' | ' ' => {
-                diff_text.push(origin);
-                diff_text.push_str(content);
+    ["id_ed25519", "id_rsa"]
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .find(|path| path.is_file())
+}
+
+fn create_remote_callbacks(auth: &AuthConfig) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        // libgit2 keeps asking while credentials are rejected; give up eventually
+        attempts += 1;
+        if attempts > MAX_CREDENTIAL_ATTEMPTS {
+            return Err(git2::Error::new(
+                git2::ErrorCode::Auth,
+                git2::ErrorClass::Callback,
+                "credentials were rejected",
+            ));
+        }
+
+        let username = username_from_url.unwrap_or("git");
+        match auth {
+            AuthConfig::SshAgent => {
+                if allowed_types.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+                    let config = git2::Config::open_default()?;
+                    Cred::credential_helper(&config, url, username_from_url)
+                } else {
+                    ssh_credential(username, attempts)
+                }
             }
-            _ => {
-                // For other lines (headers, etc), include as-is
-                diff_text.push_str(content);
+            AuthConfig::SshKey { path, passphrase } => {
+                Cred::ssh_key(username, None, Path::new(path), passphrase.as_deref())
+            }
+            AuthConfig::HttpsToken(token) => Cred::userpass_plaintext(username, token),
+            AuthConfig::HttpsPassword { username, password } => {
+                Cred::userpass_plaintext(username, password)
             }
         }
+    });
+    callbacks.transfer_progress(|stats| {
+        println!(
+            "Received {}/{} objects ({} bytes)",
+            stats.received_objects(),
+            stats.total_objects(),
+            stats.received_bytes()
+        );
         true
-    })?;
-
-    Ok(diff_text)
+    });
+    callbacks
 }
 
-pub fn ignore_file(repo: &Repository, file_path: &str) -> Result<(), git2::Error> {
-    let gitignore_path = repo.workdir().unwrap().join(".gitignore");
-
-    let mut content = if gitignore_path.exists() {
-        std::fs::read_to_string(&gitignore_path)
-            .map_err(|e| git2::Error::from_str(&format!("Failed to read .gitignore: {}", e)))?
+pub fn clone_repository(
+    url: &str,
+    path: &str,
+    auth: &AuthConfig,
+    options: &CloneOptions,
+    progress: Option<Box<dyn Fn(CloneProgress) + Send>>,
+) -> Result<(), GitError> {
+    // --single-branch without --branch follows the remote's HEAD
+    let single_branch = if options.single_branch {
+        match &options.branch {
+            Some(branch) => Some(branch.clone()),
+            None => Some(remote_default_branch(url, auth)?),
+        }
     } else {
-        String::new()
+        None
     };
 
-    if !content.is_empty() && !content.ends_with('\n') {
-        content.push('\n');
+    let mut callbacks = create_remote_callbacks(auth);
+    if let Some(progress) = progress {
+        callbacks.transfer_progress(move |stats| {
+            progress(CloneProgress {
+                received_objects: stats.received_objects(),
+                total_objects: stats.total_objects(),
+                received_bytes: stats.received_bytes(),
+                indexed_deltas: stats.indexed_deltas(),
+                total_deltas: stats.total_deltas(),
+            });
+            true
+        });
     }
 
-    content.push_str(file_path);
-    content.push('\n');
-
-    std::fs::write(&gitignore_path, content)
-        .map_err(|e| git2::Error::from_str(&format!("Failed to write .gitignore: {}", e)))?;
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    if let Some(depth) = options.depth {
+        fetch_options.depth(depth as i32);
+    }
 
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch_options);
+    if let Some(branch) = single_branch.as_ref().or(options.branch.as_ref()) {
+        builder.branch(branch);
+    }
+    if let Some(branch) = single_branch.clone() {
+        builder.remote_create(move |repo, name, url| {
+            let refspec = format!("+refs/heads/{0}:refs/remotes/{1}/{0}", branch, name);
+            repo.remote_with_fetch(name, url, &refspec)
+        });
+    }
+    builder.clone(url, Path::new(path))?;
     Ok(())
 }
 
-pub fn stage_file(repo: &Repository, file_path: &str) -> Result<(), git2::Error> {
-    let mut index = repo.index()?;
-    index.add_path(std::path::Path::new(file_path))?;
-    index.write()?;
+fn remote_default_branch(url: &str, auth: &AuthConfig) -> Result<String, GitError> {
+    let mut remote = git2::Remote::create_detached(url)?;
+    let connection = remote.connect_auth(
+        git2::Direction::Fetch,
+        Some(create_remote_callbacks(auth)),
+        None,
+    )?;
+    let default_branch = connection.default_branch()?;
+    let name = default_branch
+        .as_str()
+        .and_then(|name| name.strip_prefix("refs/heads/"))
+        .ok_or_else(|| GitError::Unknown("Remote has no default branch".to_string()))?;
+    Ok(name.to_string())
+}
+
+pub fn ls_remote(url: &str) -> Result<Vec<RemoteRef>, GitError> {
+    let mut remote = git2::Remote::create_detached(url)?;
+    let auth = AuthConfig::default();
+    let connection = remote.connect_auth(
+        git2::Direction::Fetch,
+        Some(create_remote_callbacks(&auth)),
+        None,
+    )?;
+
+    let refs = connection
+        .list()?
+        .iter()
+        .map(|head| RemoteRef {
+            name: head.name().to_string(),
+            oid: head.oid().to_string(),
+        })
+        .collect();
+
+    Ok(refs)
+}
+
+pub fn is_git_repository(path: &str) -> bool {
+    let path = Path::new(path);
+    if path.join(".git").exists() {
+        return true;
+    }
+
+    // Bare repositories keep HEAD, objects and refs at the top level
+    path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir()
+}
+
+pub fn init_repository(
+    path: &str,
+    bare: bool,
+    initial_branch: Option<&str>,
+) -> Result<(), GitError> {
+    let mut opts = git2::RepositoryInitOptions::new();
+    opts.bare(bare);
+    if let Some(branch) = initial_branch {
+        opts.initial_head(branch);
+    }
+
+    Repository::init_opts(path, &opts)?;
     Ok(())
 }
 
-pub fn stage_hunk(
-    repo: &Repository,
-    _file_path: &str,
-    full_diff: &str,
-    hunk_header: &str,
-    hunk_lines: &str,
-) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
+pub fn open_repository(path: &str) -> Result<Repository, GitError> {
+    Ok(Repository::open(path)?)
+}
 
-    // Extract the first 4 lines as diff header (like GitX does)
-    let diff_lines: Vec<&str> = full_diff.lines().collect();
-    let diff_header = diff_lines
-        .iter()
-        .take(4)
-        .cloned()
-        .collect::<Vec<&str>>()
-        .join("\n");
+pub fn get_repository_info(repo: &Repository) -> Result<RepositoryInfo, GitError> {
+    // Bare repositories have no workdir; fall back to the git dir itself
+    let root = repo.workdir().unwrap_or_else(|| repo.path());
+    let name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().trim_end_matches(".git").to_string())
+        .unwrap_or_default();
+
+    // Ignore the placeholder text `git init` writes
+    let description = std::fs::read_to_string(repo.path().join("description"))
+        .ok()
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty() && !d.starts_with("Unnamed repository;"));
+
+    // libgit2 only treats an unborn *default* branch as empty
+    let is_empty = repo.is_empty()?
+        || (find_head_commit(repo)?.is_none() && repo.references()?.next().is_none());
+
+    Ok(RepositoryInfo {
+        name,
+        path: root.to_string_lossy().trim_end_matches('/').to_string(),
+        description,
+        is_bare: repo.is_bare(),
+        is_shallow: repo.is_shallow(),
+        is_empty,
+        state: repository_state_name(repo.state()).to_string(),
+    })
+}
 
-    // Create patch: header + hunk header + hunk lines
-    let patch = format!("{}\n{}\n{}\n", diff_header, hunk_header, hunk_lines);
+pub fn get_head_info(repo: &Repository) -> Result<HeadInfo, GitError> {
+    let is_detached = repo.head_detached()?;
 
-    eprintln!("=== STAGE PATCH ===");
-    eprintln!("{}", patch);
-    eprintln!("=== END ===");
+    // Read the symbolic ref directly so an unborn branch still reports its name
+    let branch_name = if is_detached {
+        None
+    } else {
+        repo.find_reference("HEAD")?
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(|name| name.to_string())
+    };
 
-    // Use git apply command with --unidiff-zero and --cached
-    let output = std::process::Command::new("git")
-        .args(["apply", "--unidiff-zero", "--cached", "--ignore-whitespace"])
-        .current_dir(workdir)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .and_then(|mut child| {
-            use std::io::Write;
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(patch.as_bytes())?;
-            }
-            child.wait_with_output()
-        })
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git apply: {}", e)))?;
+    // Unborn branches have no commit yet
+    let commit_id = find_head_commit(repo)?
+        .map(|commit| commit.id().to_string())
+        .unwrap_or_default();
+
+    Ok(HeadInfo {
+        branch_name,
+        commit_id,
+        is_detached,
+        state: repository_state_name(repo.state()).to_string(),
+    })
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(&format!(
-            "git apply failed: {}",
-            stderr
-        )));
+pub fn get_repository_state(repo: &Repository) -> Result<RepoState, GitError> {
+    let read_count = |file: &str| {
+        std::fs::read_to_string(repo.path().join(file))
+            .ok()
+            .and_then(|contents| contents.trim().parse::<usize>().ok())
+    };
+
+    // Merge-based rebases track progress in rebase-merge/, `git am` style ones in rebase-apply/
+    let (step, total) = if is_rebase_in_progress(repo) {
+        match read_count("rebase-merge/msgnum") {
+            Some(step) => (Some(step), read_count("rebase-merge/end")),
+            None => (
+                read_count("rebase-apply/next"),
+                read_count("rebase-apply/last"),
+            ),
+        }
+    } else {
+        (None, None)
+    };
+
+    Ok(RepoState {
+        state: repository_state_name(repo.state()).to_string(),
+        step,
+        total,
+    })
+}
+
+fn repository_state_name(state: git2::RepositoryState) -> &'static str {
+    match state {
+        git2::RepositoryState::Clean => "clean",
+        git2::RepositoryState::Merge => "merging",
+        git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence => "reverting",
+        git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence => {
+            "cherry-picking"
+        }
+        git2::RepositoryState::Bisect => "bisecting",
+        git2::RepositoryState::Rebase
+        | git2::RepositoryState::RebaseInteractive
+        | git2::RepositoryState::RebaseMerge => "rebasing",
+        git2::RepositoryState::ApplyMailbox | git2::RepositoryState::ApplyMailboxOrRebase => {
+            "applying patches"
+        }
+    }
+}
+
+pub fn get_repository_size(repo: &Repository) -> Result<RepoSize, GitError> {
+    let mut size = RepoSize {
+        loose_objects: 0,
+        pack_files: 0,
+        total_bytes: 0,
+    };
+    scan_objects_dir(&repo.path().join("objects"), &mut size)
+        .map_err(|e| GitError::Unknown(format!("Failed to scan objects: {}", e)))?;
+    Ok(size)
+}
+
+fn scan_objects_dir(dir: &Path, size: &mut RepoSize) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+
+        if metadata.is_dir() {
+            scan_objects_dir(&path, size)?;
+            continue;
+        }
+
+        size.total_bytes += metadata.len();
+
+        // Loose objects live in two-hex-digit fan-out directories
+        let in_fanout_dir = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.len() == 2 && n.chars().all(|c| c.is_ascii_hexdigit()));
+        if in_fanout_dir {
+            size.loose_objects += 1;
+        } else if path.extension().is_some_and(|ext| ext == "pack") {
+            size.pack_files += 1;
+        }
     }
 
     Ok(())
 }
 
-pub fn unstage_file(repo: &Repository, file_path: &str) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
+pub fn run_gc(repo: &Repository, aggressive: bool) -> Result<(), GitError> {
+    let mut args = vec!["gc", "--quiet"];
+    if aggressive {
+        args.push("--aggressive");
+    }
 
-    // Use git reset HEAD <file> to unstage
+    // git gc also works on bare repositories, which have no workdir
     let output = std::process::Command::new("git")
-        .args(["reset", "HEAD", file_path])
-        .current_dir(workdir)
+        .args(&args)
+        .current_dir(repo.path())
         .output()
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git reset: {}", e)))?;
+        .map_err(|e| GitError::Unknown(format!("Failed to run git gc: {}", e)))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(&format!(
-            "git reset failed: {}",
-            stderr
-        )));
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
     }
 
     Ok(())
 }
 
-pub fn unstage_hunk(
-    repo: &Repository,
-    _file_path: &str,
-    full_diff: &str,
-    hunk_header: &str,
-    hunk_lines: &str,
-) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
-
-    // Extract the first 4 lines as diff header (like GitX does)
-    let diff_lines: Vec<&str> = full_diff.lines().collect();
-    let diff_header = diff_lines
-        .iter()
-        .take(4)
-        .cloned()
-        .collect::<Vec<&str>>()
-        .join("\n");
+pub fn count_objects(repo: &Repository) -> Result<ObjectCount, GitError> {
+    let output = std::process::Command::new("git")
+        .args(["count-objects", "-v"])
+        .current_dir(repo.path())
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git count-objects: {}", e)))?;
 
-    // Create patch: header + hunk header + hunk lines
-    let patch = format!("{}\n{}\n{}\n", diff_header, hunk_header, hunk_lines);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
 
-    eprintln!("=== UNSTAGE PATCH ===");
+    let mut counts = ObjectCount::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once(": ") else {
+            continue;
+        };
+        let value = value.trim().parse().unwrap_or(0);
+        match key {
+            "count" => counts.count = value,
+            "size" => counts.size = value,
+            "in-pack" => counts.in_pack = value,
+            "packs" => counts.packs = value,
+            "size-pack" => counts.size_pack = value,
+            "prune-packable" => counts.prune_packable = value,
+            "garbage" => counts.garbage = value,
+            _ => {}
+        }
+    }
+
+    Ok(counts)
+}
+
+pub fn get_pack_info(repo: &Repository) -> Result<Vec<PackInfo>, GitError> {
+    let pack_dir = repo.path().join("objects").join("pack");
+    if !pack_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = std::fs::read_dir(&pack_dir)
+        .map_err(|e| GitError::Unknown(format!("Failed to read pack directory: {}", e)))?;
+
+    let mut packs = Vec::new();
+    for entry in entries.flatten() {
+        let idx_path = entry.path();
+        if idx_path.extension().and_then(|e| e.to_str()) != Some("idx") {
+            continue;
+        }
+        let Some(name) = idx_path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        let idx = std::fs::read(&idx_path)
+            .map_err(|e| GitError::Unknown(format!("Failed to read {}: {}", name, e)))?;
+        let size_bytes = std::fs::metadata(idx_path.with_extension("pack"))
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        packs.push(PackInfo {
+            name: name.to_string(),
+            objects: pack_index_object_count(&idx),
+            size_bytes,
+        });
+    }
+    packs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(packs)
+}
+
+// The last entry of the 256-entry fanout table is the total object count.
+// Version 2 indexes put an 8-byte header in front of the table.
+fn pack_index_object_count(idx: &[u8]) -> u64 {
+    let fanout_start = if idx.starts_with(b"\xfftOc") { 8 } else { 0 };
+    let last = fanout_start + 255 * 4;
+    idx.get(last..last + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64)
+        .unwrap_or(0)
+}
+
+pub fn verify_repository(repo: &Repository) -> Result<FsckResult, GitError> {
+    let output = std::process::Command::new("git")
+        .args(["fsck", "--strict"])
+        .current_dir(repo.path())
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git fsck: {}", e)))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for line in stderr.lines() {
+        if let Some(fatal) = line.strip_prefix("fatal: ") {
+            return Err(GitError::Unknown(fatal.to_string()));
+        } else if let Some(error) = line.strip_prefix("error: ") {
+            errors.push(error.to_string());
+        } else if let Some(warning) = line.strip_prefix("warning: ") {
+            warnings.push(warning.to_string());
+        }
+    }
+
+    // Missing objects are reported on stdout and only show in the exit code
+    let ok = output.status.success() && errors.is_empty();
+    Ok(FsckResult {
+        errors,
+        warnings,
+        ok,
+    })
+}
+
+pub fn get_archive(
+    repo: &Repository,
+    tree_ish: &str,
+    format: ArchiveFormat,
+    output_path: &str,
+) -> Result<(), GitError> {
+    // Resolve against our own working directory rather than the repository's
+    let output_path = std::path::absolute(output_path)
+        .map_err(|e| GitError::Unknown(format!("Invalid output path: {}", e)))?;
+    // Pass the resolved object rather than the spec so it can't be read as an option
+    let target = repo.revparse_single(tree_ish)?;
+    target.peel_to_tree()?;
+
+    let mut args = vec![
+        "archive".to_string(),
+        "-o".to_string(),
+        output_path.to_string_lossy().to_string(),
+    ];
+    match format {
+        ArchiveFormat::Zip => args.push("--format=zip".to_string()),
+        ArchiveFormat::TarGz => {
+            // Tarballs conventionally extract into a directory named after the project
+            let name = get_repository_info(repo)?.name;
+            args.push("--format=tar.gz".to_string());
+            args.push(format!("--prefix={}/", name));
+        }
+    }
+    args.push(target.id().to_string());
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .current_dir(repo.workdir().unwrap_or(repo.path()))
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git archive: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn get_branches(repo: &Repository) -> Result<Vec<GitBranch>, GitError> {
+    let mut branches = Vec::new();
+    let is_detached = repo.head_detached()?;
+    // A freshly initialized repository has an unborn HEAD and no branches yet
+    let head = repo.head().ok();
+    let head_name = head.as_ref().and_then(|h| h.shorthand());
+
+    // Add detached HEAD as a special branch if applicable
+    if is_detached {
+        branches.push(GitBranch {
+            name: "HEAD (detached)".to_string(),
+            is_head: true,
+            upstream: None,
+            ahead: None,
+            behind: None,
+        });
+    }
+
+    for branch in repo.branches(Some(git2::BranchType::Local))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            // Tracking info is only available when an upstream is configured
+            let mut upstream = None;
+            let mut ahead = None;
+            let mut behind = None;
+            if let Ok(upstream_branch) = branch.upstream() {
+                upstream = upstream_branch.name()?.map(|n| n.to_string());
+                if let (Some(local_oid), Some(upstream_oid)) =
+                    (branch.get().target(), upstream_branch.get().target())
+                {
+                    let (a, b) = repo.graph_ahead_behind(local_oid, upstream_oid)?;
+                    ahead = Some(a);
+                    behind = Some(b);
+                }
+            }
+
+            branches.push(GitBranch {
+                name: name.to_string(),
+                is_head: !is_detached && Some(name) == head_name,
+                upstream,
+                ahead,
+                behind,
+            });
+        }
+    }
+
+    Ok(branches)
+}
+
+pub fn get_branch_head(repo: &Repository, branch_name: &str) -> Result<String, GitError> {
+    // Try local branch first
+    if let Ok(branch) = repo.find_branch(branch_name, git2::BranchType::Local) {
+        let commit = branch.get().peel_to_commit()?;
+        return Ok(commit.id().to_string());
+    }
+
+    // Try remote branch
+    if let Ok(branch) = repo.find_branch(branch_name, git2::BranchType::Remote) {
+        let commit = branch.get().peel_to_commit()?;
+        return Ok(commit.id().to_string());
+    }
+
+    // Try as a reference (for remote branches like origin/branch)
+    let ref_name = format!("refs/remotes/{}", branch_name);
+    if let Ok(reference) = repo.find_reference(&ref_name) {
+        let commit = reference.peel_to_commit()?;
+        return Ok(commit.id().to_string());
+    }
+
+    Err(GitError::BranchNotFound(branch_name.to_string()))
+}
+
+pub fn get_ahead_behind(
+    repo: &Repository,
+    local: &str,
+    upstream: &str,
+) -> Result<(usize, usize), GitError> {
+    let local_oid = repo.revparse_single(local)?.peel_to_commit()?.id();
+    let upstream_oid = repo.revparse_single(upstream)?.peel_to_commit()?.id();
+    Ok(repo.graph_ahead_behind(local_oid, upstream_oid)?)
+}
+
+pub fn get_tracking_branch(
+    repo: &Repository,
+    branch_name: &str,
+) -> Result<Option<String>, GitError> {
+    let branch = repo.find_branch(branch_name, git2::BranchType::Local)?;
+    match branch.upstream() {
+        Ok(upstream) => Ok(upstream.name()?.map(|n| n.to_string())),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn set_tracking_branch(
+    repo: &Repository,
+    branch_name: &str,
+    remote: &str,
+    remote_branch: &str,
+) -> Result<(), GitError> {
+    // Fail early for unknown branches or remotes instead of writing dangling config
+    repo.find_branch(branch_name, git2::BranchType::Local)?;
+    repo.find_remote(remote)?;
+
+    let mut config = repo.config()?;
+    config.set_str(&format!("branch.{}.remote", branch_name), remote)?;
+    Ok(config.set_str(
+        &format!("branch.{}.merge", branch_name),
+        &format!("refs/heads/{}", remote_branch),
+    )?)
+}
+
+pub fn unset_tracking_branch(repo: &Repository, branch_name: &str) -> Result<(), GitError> {
+    let mut config = repo.config()?;
+    for key in ["remote", "merge"] {
+        match config.remove(&format!("branch.{}.{}", branch_name, key)) {
+            Ok(()) => {}
+            Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+pub fn get_merge_base(
+    repo: &Repository,
+    commit_a: &str,
+    commit_b: &str,
+) -> Result<String, GitError> {
+    let a = repo.revparse_single(commit_a)?.peel_to_commit()?.id();
+    let b = repo.revparse_single(commit_b)?.peel_to_commit()?.id();
+    Ok(repo.merge_base(a, b)?.to_string())
+}
+
+pub fn get_merge_base_many(repo: &Repository, commits: Vec<&str>) -> Result<String, GitError> {
+    if commits.len() < 2 {
+        return Err(GitError::Unknown(
+            "At least two commits are required to compute a merge base".to_string(),
+        ));
+    }
+
+    let oids = commits
+        .iter()
+        .map(|spec| Ok(repo.revparse_single(spec)?.peel_to_commit()?.id()))
+        .collect::<Result<Vec<_>, GitError>>()?;
+    Ok(repo.merge_base_many(&oids)?.to_string())
+}
+
+pub fn create_branch(
+    repo: &Repository,
+    name: &str,
+    start_point: Option<&str>,
+) -> Result<GitBranch, GitError> {
+    let target = repo
+        .revparse_single(start_point.unwrap_or("HEAD"))?
+        .peel_to_commit()?;
+    let branch = repo.branch(name, &target, false)?;
+
+    Ok(GitBranch {
+        name: branch.name()?.unwrap_or(name).to_string(),
+        is_head: branch.is_head(),
+        upstream: None,
+        ahead: None,
+        behind: None,
+    })
+}
+
+pub fn delete_branch(repo: &Repository, name: &str, force: bool) -> Result<(), GitError> {
+    let mut branch = repo.find_branch(name, git2::BranchType::Local)?;
+
+    if branch.is_head() {
+        if !force {
+            return Err(GitError::Unknown(format!(
+                "Cannot delete branch '{}' because it is currently checked out",
+                name
+            )));
+        }
+        // libgit2 refuses to delete the checked out branch, so drop the ref directly
+        return Ok(branch.get_mut().delete()?);
+    }
+
+    Ok(branch.delete()?)
+}
+
+pub fn rename_branch(repo: &Repository, old_name: &str, new_name: &str) -> Result<(), GitError> {
+    let mut branch = repo.find_branch(old_name, git2::BranchType::Local)?;
+
+    // Remember the upstream so tracking survives the rename
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream.name()?.map(|name| name.to_string()),
+        Err(_) => None,
+    };
+
+    let mut renamed = branch.rename(new_name, false)?;
+
+    if let Some(upstream) = upstream {
+        if renamed.upstream().is_err() {
+            renamed.set_upstream(Some(&upstream))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn get_remotes(repo: &Repository) -> Result<Vec<GitRemote>, GitError> {
+    let mut remotes = Vec::new();
+
+    for name in repo.remotes()?.iter().flatten() {
+        if let Ok(remote) = repo.find_remote(name) {
+            if let Some(url) = remote.url() {
+                remotes.push(GitRemote {
+                    name: name.to_string(),
+                    url: url.to_string(),
+                    push_url: remote.pushurl().map(|u| u.to_string()),
+                });
+            }
+        }
+    }
+
+    Ok(remotes)
+}
+
+pub fn add_remote(repo: &Repository, name: &str, url: &str) -> Result<(), GitError> {
+    repo.remote(name, url)?;
+    Ok(())
+}
+
+pub fn remove_remote(repo: &Repository, name: &str) -> Result<(), GitError> {
+    Ok(repo.remote_delete(name)?)
+}
+
+pub fn rename_remote(repo: &Repository, old_name: &str, new_name: &str) -> Result<(), GitError> {
+    // Non-default fetch refspecs that couldn't be renamed are left for the user
+    repo.remote_rename(old_name, new_name)?;
+    Ok(())
+}
+
+pub fn set_remote_url(repo: &Repository, name: &str, url: &str) -> Result<(), GitError> {
+    Ok(repo.remote_set_url(name, url)?)
+}
+
+pub fn branch_contains_commit(
+    repo: &Repository,
+    branch_name: &str,
+    commit_id: &str,
+) -> Result<bool, GitError> {
+    let branch = match repo.find_branch(branch_name, git2::BranchType::Local) {
+        Ok(branch) => branch,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            repo.find_branch(branch_name, git2::BranchType::Remote)?
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let head = branch.get().peel_to_commit()?.id();
+    let target = repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+
+    // graph_descendant_of is false for the commit itself
+    Ok(head == target || repo.graph_descendant_of(head, target)?)
+}
+
+pub fn get_branches_containing(
+    repo: &Repository,
+    commit_id: &str,
+    remote: bool,
+) -> Result<Vec<String>, GitError> {
+    let target = repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+    let branch_type = if remote {
+        None
+    } else {
+        Some(git2::BranchType::Local)
+    };
+
+    let mut branches = Vec::new();
+    for branch in repo.branches(branch_type)? {
+        let (branch, _) = branch?;
+        let Some(name) = branch.name()? else {
+            continue;
+        };
+        // Symbolic refs like origin/HEAD would duplicate the branch they point at
+        if branch.get().kind() != Some(git2::ReferenceType::Direct) {
+            continue;
+        }
+        let Some(head) = branch.get().target() else {
+            continue;
+        };
+        if head == target || repo.graph_descendant_of(head, target)? {
+            branches.push(name.to_string());
+        }
+    }
+
+    branches.sort();
+    Ok(branches)
+}
+
+pub fn get_remote_branches(repo: &Repository, remote_name: &str) -> Result<Vec<String>, GitError> {
+    let mut branches = Vec::new();
+    let prefix = format!("{}/", remote_name);
+
+    for branch in repo.branches(Some(git2::BranchType::Remote))? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            if name.starts_with(&prefix) {
+                let branch_name = name.strip_prefix(&prefix).unwrap_or(name);
+                if branch_name != "HEAD" {
+                    branches.push(branch_name.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(branches)
+}
+
+// Remote-tracking refs as of the last fetch; no network access
+pub fn get_remote_refs_cached(
+    repo: &Repository,
+    remote_name: &str,
+) -> Result<Vec<RemoteRef>, GitError> {
+    repo.find_remote(remote_name)?;
+
+    let mut refs = Vec::new();
+    for reference in repo.references_glob(&format!("refs/remotes/{}/*", remote_name))? {
+        let reference = reference?;
+        let Some(name) = reference.name() else {
+            continue;
+        };
+        // refs/remotes/<remote>/HEAD is symbolic; report what it points at
+        let Some(oid) = reference.resolve()?.target() else {
+            continue;
+        };
+        refs.push(RemoteRef {
+            name: name.to_string(),
+            oid: oid.to_string(),
+        });
+    }
+
+    Ok(refs)
+}
+
+pub fn update_ref(
+    repo: &Repository,
+    ref_name: &str,
+    target_oid: &str,
+    force: bool,
+) -> Result<(), GitError> {
+    let oid = git2::Oid::from_str(target_oid)
+        .map_err(|e| GitError::Unknown(format!("Invalid object ID: {}", e)))?;
+    // Don't leave a ref pointing at an object we don't have
+    repo.find_object(oid, None)?;
+
+    repo.reference(ref_name, oid, force, "")?;
+    Ok(())
+}
+
+pub fn read_symbolic_ref(repo: &Repository, ref_name: &str) -> Result<String, GitError> {
+    let reference = repo.find_reference(ref_name)?;
+    reference
+        .symbolic_target()
+        .map(|target| target.to_string())
+        .ok_or_else(|| GitError::Unknown(format!("'{}' is not a symbolic ref", ref_name)))
+}
+
+pub fn set_symbolic_ref(
+    repo: &Repository,
+    ref_name: &str,
+    target_ref: &str,
+    force: bool,
+) -> Result<(), GitError> {
+    repo.reference_symbolic(ref_name, target_ref, force, "")?;
+    Ok(())
+}
+
+pub fn get_tags(repo: &Repository) -> Result<Vec<String>, GitError> {
+    let mut tags = Vec::new();
+
+    repo.tag_foreach(|_oid, name| {
+        if let Ok(name_str) = std::str::from_utf8(name) {
+            if let Some(tag_name) = name_str.strip_prefix("refs/tags/") {
+                tags.push(tag_name.to_string());
+            }
+        }
+        true
+    })?;
+
+    tags.sort();
+    Ok(tags)
+}
+
+pub fn get_tag_commit(repo: &Repository, tag_name: &str) -> Result<String, GitError> {
+    let reference = repo.find_reference(&format!("refs/tags/{}", tag_name))?;
+    let target = reference.peel_to_commit()?;
+    Ok(target.id().to_string())
+}
+
+pub fn get_tags_containing(repo: &Repository, commit_id: &str) -> Result<Vec<String>, GitError> {
+    let target = repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+
+    let mut tags = Vec::new();
+    for tag_name in get_tags(repo)? {
+        // Tags pointing at trees or blobs can't contain a commit
+        let Ok(tagged) = get_tag_commit(repo, &tag_name) else {
+            continue;
+        };
+        let tagged = git2::Oid::from_str(&tagged)?;
+        if tagged == target || repo.graph_descendant_of(tagged, target)? {
+            tags.push(tag_name);
+        }
+    }
+
+    Ok(tags)
+}
+
+pub fn get_annotated_tag_info(repo: &Repository, tag_name: &str) -> Result<TagInfo, GitError> {
+    let reference = repo.find_reference(&format!("refs/tags/{}", tag_name))?;
+
+    // Lightweight tags point straight at the commit and carry no metadata
+    let Ok(tag) = reference.peel_to_tag() else {
+        let target = reference.peel_to_commit()?;
+        return Ok(TagInfo {
+            name: tag_name.to_string(),
+            target_oid: target.id().to_string(),
+            is_annotated: false,
+            tagger_name: None,
+            tagger_email: None,
+            tagger_date: None,
+            message: None,
+        });
+    };
+
+    let tagger = tag.tagger();
+    Ok(TagInfo {
+        name: tag_name.to_string(),
+        target_oid: tag.target_id().to_string(),
+        is_annotated: true,
+        tagger_name: tagger
+            .as_ref()
+            .and_then(|t| t.name().map(|n| n.to_string())),
+        tagger_email: tagger
+            .as_ref()
+            .and_then(|t| t.email().map(|e| e.to_string())),
+        tagger_date: tagger.as_ref().map(|t| t.when().seconds().to_string()),
+        message: tag.message().map(|m| m.to_string()),
+    })
+}
+
+pub fn describe_commit(repo: &Repository, commit_id: &str, tags: bool) -> Result<String, GitError> {
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+
+    let mut opts = git2::DescribeOptions::new();
+    if tags {
+        opts.describe_tags();
+    } else {
+        opts.describe_all();
+    }
+
+    let description = commit.as_object().describe(&opts)?;
+    Ok(description.format(None)?)
+}
+
+// notes_ref of None means the default, refs/notes/commits
+pub fn get_notes(
+    repo: &Repository,
+    commit_id: &str,
+    notes_ref: Option<&str>,
+) -> Result<Option<String>, GitError> {
+    let oid = repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+
+    match repo.find_note(notes_ref, oid) {
+        Ok(note) => Ok(note.message().map(|m| m.to_string())),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn add_note(
+    repo: &Repository,
+    commit_id: &str,
+    message: &str,
+    notes_ref: Option<&str>,
+) -> Result<(), GitError> {
+    let oid = repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+
+    let signature = repo.signature()?;
+    repo.note(&signature, &signature, notes_ref, oid, message, false)?;
+    Ok(())
+}
+
+pub fn remove_note(
+    repo: &Repository,
+    commit_id: &str,
+    notes_ref: Option<&str>,
+) -> Result<(), GitError> {
+    let oid = repo.revparse_single(commit_id)?.peel_to_commit()?.id();
+
+    let signature = repo.signature()?;
+    Ok(repo.note_delete(oid, notes_ref, &signature, &signature)?)
+}
+
+pub fn create_tag(
+    repo: &Repository,
+    name: &str,
+    target_ish: &str,
+    message: Option<&str>,
+    tagger: Option<GitSignature>,
+) -> Result<(), GitError> {
+    let target = repo.revparse_single(target_ish)?;
+
+    match message {
+        // A message makes it an annotated tag
+        Some(message) => {
+            let tagger = match tagger {
+                Some(sig) => git2::Signature::now(&sig.name, &sig.email)?,
+                None => repo.signature()?,
+            };
+            repo.tag(name, &target, &tagger, message, false)?;
+        }
+        None => {
+            repo.tag_lightweight(name, &target, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn delete_tag(repo: &Repository, name: &str) -> Result<(), GitError> {
+    Ok(repo
+        .find_reference(&format!("refs/tags/{}", name))?
+        .delete()?)
+}
+
+pub fn get_submodules(repo: &Repository) -> Result<Vec<GitSubmodule>, GitError> {
+    let mut submodules = Vec::new();
+
+    for submodule in repo.submodules()? {
+        if let (Some(name), Some(url)) = (submodule.name(), submodule.url()) {
+            let path = submodule.path();
+            submodules.push(GitSubmodule {
+                name: name.to_string(),
+                path: path.to_str().unwrap_or("").to_string(),
+                url: url.to_string(),
+            });
+        }
+    }
+
+    Ok(submodules)
+}
+
+pub fn add_submodule(repo: &Repository, url: &str, path: &str) -> Result<(), GitError> {
+    let mut submodule = repo.submodule(url, Path::new(path), true)?;
+
+    // A freshly set up submodule has no index entry yet, so `update` can't be
+    // used; clone it directly instead
+    let auth = AuthConfig::default();
+    submodule.clone(Some(&mut submodule_update_options(&auth)))?;
+
+    // Stages .gitmodules and the submodule's gitlink
+    Ok(submodule.add_finalize()?)
+}
+
+pub fn init_submodule(repo: &Repository, name: &str) -> Result<(), GitError> {
+    Ok(repo.find_submodule(name)?.init(false)?)
+}
+
+pub fn update_submodule(repo: &Repository, name: &str, recursive: bool) -> Result<(), GitError> {
+    let mut submodule = repo.find_submodule(name)?;
+    update_submodule_checkout(&mut submodule, recursive)
+}
+
+fn update_submodule_checkout(
+    submodule: &mut git2::Submodule,
+    recursive: bool,
+) -> Result<(), GitError> {
+    let auth = AuthConfig::default();
+    submodule.update(true, Some(&mut submodule_update_options(&auth)))?;
+
+    if recursive {
+        let sub_repo = submodule.open()?;
+        for mut nested in sub_repo.submodules()? {
+            update_submodule_checkout(&mut nested, true)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn submodule_update_options(auth: &AuthConfig) -> git2::SubmoduleUpdateOptions<'_> {
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(create_remote_callbacks(auth));
+    let mut update_options = git2::SubmoduleUpdateOptions::new();
+    update_options.fetch(fetch_options);
+    update_options
+}
+
+pub fn deinit_submodule(repo: &Repository, name: &str, force: bool) -> Result<(), GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+    let submodule = repo.find_submodule(name)?;
+    let path = submodule.path().to_string_lossy().to_string();
+
+    let mut args = vec!["submodule", "deinit"];
+    if force {
+        args.push("--force");
+    }
+    args.push("--");
+    args.push(&path);
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git submodule deinit: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn get_submodule_status(repo: &Repository) -> Result<Vec<GitSubmoduleStatus>, GitError> {
+    let conflicts = conflicted_paths(&repo.index()?)?;
+    let mut statuses = Vec::new();
+
+    for submodule in repo.submodules()? {
+        let Some(name) = submodule.name() else {
+            continue;
+        };
+        let path = submodule.path().to_string_lossy().to_string();
+        let flags = repo.submodule_status(name, git2::SubmoduleIgnore::None)?;
+
+        let status = if conflicts.contains(&path) {
+            SubmoduleStatus::Conflict
+        } else if flags.contains(git2::SubmoduleStatus::WD_UNINITIALIZED) {
+            SubmoduleStatus::Uninitialized
+        } else if flags
+            .intersects(git2::SubmoduleStatus::WD_MODIFIED | git2::SubmoduleStatus::INDEX_MODIFIED)
+        {
+            // Checked out at a different commit than the superproject records
+            SubmoduleStatus::OutOfDate
+        } else if flags.intersects(
+            git2::SubmoduleStatus::WD_INDEX_MODIFIED
+                | git2::SubmoduleStatus::WD_WD_MODIFIED
+                | git2::SubmoduleStatus::WD_UNTRACKED,
+        ) {
+            SubmoduleStatus::Modified
+        } else {
+            SubmoduleStatus::Clean
+        };
+
+        statuses.push(GitSubmoduleStatus {
+            name: name.to_string(),
+            path,
+            url: submodule.url().unwrap_or("").to_string(),
+            head_oid: submodule.head_id().map(|oid| oid.to_string()),
+            workdir_oid: submodule.workdir_id().map(|oid| oid.to_string()),
+            status,
+        });
+    }
+
+    Ok(statuses)
+}
+
+pub fn get_worktrees(repo: &Repository) -> Result<Vec<GitWorktree>, GitError> {
+    // Linked worktrees share the main repository's git dir, so list from there
+    let main_repo = if repo.is_worktree() {
+        let commondir = std::fs::read_to_string(repo.path().join("commondir"))
+            .map_err(|e| GitError::Unknown(format!("Failed to read commondir: {}", e)))?;
+        Repository::open(repo.path().join(commondir.trim()))?
+    } else {
+        Repository::open(repo.path())?
+    };
+    let mut worktrees = Vec::new();
+
+    if let Some(workdir) = main_repo.workdir() {
+        worktrees.push(worktree_info(&main_repo, workdir, true, false));
+    }
+
+    for name in main_repo.worktrees()?.iter().flatten() {
+        let worktree = main_repo.find_worktree(name)?;
+        let is_locked = matches!(worktree.is_locked()?, git2::WorktreeLockStatus::Locked(_));
+
+        // Worktrees whose directory was deleted can't be opened; show them anyway
+        let info = match Repository::open_from_worktree(&worktree) {
+            Ok(wt_repo) => worktree_info(&wt_repo, worktree.path(), false, is_locked),
+            Err(_) => GitWorktree {
+                path: worktree.path().to_string_lossy().to_string(),
+                branch: String::new(),
+                head: String::new(),
+                is_main: false,
+                is_locked,
+            },
+        };
+        worktrees.push(info);
+    }
+
+    Ok(worktrees)
+}
+
+fn worktree_info(repo: &Repository, path: &Path, is_main: bool, is_locked: bool) -> GitWorktree {
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .filter(|h| h.is_branch())
+        .and_then(|h| h.shorthand())
+        .unwrap_or("")
+        .to_string();
+    let head_id = head
+        .and_then(|h| h.target())
+        .map(|oid| oid.to_string())
+        .unwrap_or_default();
+
+    GitWorktree {
+        path: path.to_string_lossy().trim_end_matches('/').to_string(),
+        branch,
+        head: head_id,
+        is_main,
+        is_locked,
+    }
+}
+
+pub fn add_worktree(repo: &Repository, path: &str, branch: &str) -> Result<(), GitError> {
+    let worktree_path = Path::new(path);
+    if worktree_path.exists() {
+        return Err(GitError::Unknown(format!("Path {} already exists", path)));
+    }
+
+    // The worktree's admin directory is named after the last path component
+    let name = worktree_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| GitError::Unknown("Invalid worktree path".to_string()))?;
+
+    let branch_ref = repo
+        .find_branch(branch, git2::BranchType::Local)?
+        .into_reference();
+    let mut opts = git2::WorktreeAddOptions::new();
+    opts.reference(Some(&branch_ref));
+
+    repo.worktree(name, worktree_path, Some(&opts))?;
+    Ok(())
+}
+
+pub fn remove_worktree(repo: &Repository, name: &str, force: bool) -> Result<(), GitError> {
+    let worktree = repo.find_worktree(name)?;
+
+    if !force {
+        if let git2::WorktreeLockStatus::Locked(_) = worktree.is_locked()? {
+            return Err(GitError::Unknown(format!("Worktree {} is locked", name)));
+        }
+
+        if let Ok(wt_repo) = Repository::open_from_worktree(&worktree) {
+            let mut status_opts = git2::StatusOptions::new();
+            status_opts.include_untracked(true);
+            if !wt_repo.statuses(Some(&mut status_opts))?.is_empty() {
+                return Err(GitError::Unknown(format!(
+                    "Worktree {} has uncommitted changes",
+                    name
+                )));
+            }
+        }
+    }
+
+    let mut prune_opts = git2::WorktreePruneOptions::new();
+    prune_opts.valid(true).working_tree(true).locked(force);
+    Ok(worktree.prune(Some(&mut prune_opts))?)
+}
+
+pub fn get_config(repo: &Repository, key: &str) -> Result<Option<String>, GitError> {
+    match repo.config()?.get_string(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn set_config(
+    repo: &Repository,
+    key: &str,
+    value: &str,
+    level: ConfigLevel,
+) -> Result<(), GitError> {
+    let mut config = repo.config()?.open_level(level.into())?;
+    Ok(config.set_str(key, value)?)
+}
+
+pub fn get_config_entries(
+    repo: &Repository,
+    glob: Option<&str>,
+    level: Option<ConfigLevel>,
+) -> Result<Vec<ConfigEntry>, GitError> {
+    let config = repo.config()?;
+    let mut entries = config.entries(glob)?;
+    let level_filter = level.map(git2::ConfigLevel::from);
+
+    let mut result = Vec::new();
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        if level_filter.is_some_and(|filter| entry.level() != filter) {
+            continue;
+        }
+
+        let level = match entry.level() {
+            git2::ConfigLevel::ProgramData => "programdata",
+            git2::ConfigLevel::System => "system",
+            git2::ConfigLevel::XDG => "xdg",
+            git2::ConfigLevel::Global => "global",
+            git2::ConfigLevel::Local => "local",
+            git2::ConfigLevel::Worktree => "worktree",
+            git2::ConfigLevel::App => "app",
+            git2::ConfigLevel::Highest => "highest",
+        };
+
+        result.push(ConfigEntry {
+            key: entry.name().unwrap_or("").to_string(),
+            value: entry.value().unwrap_or("").to_string(),
+            level: level.to_string(),
+        });
+    }
+
+    Ok(result)
+}
+
+pub fn get_status(
+    repo: &Repository,
+    rename_threshold: Option<u32>,
+) -> Result<Vec<GitFileStatus>, GitError> {
+    let mut files = Vec::new();
+    let mut status_options = git2::StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    if let Some(threshold) = rename_threshold {
+        status_options.rename_threshold(rename_threshold_percent(threshold));
+    }
+    let statuses = repo.statuses(Some(&mut status_options))?;
+    let conflict_types = get_conflict_types(&repo.index()?)?;
+
+    for entry in statuses.iter() {
+        if let Some(path) = entry.path() {
+            let status = entry.status();
+
+            // Conflicted files are listed once, as unstaged, until they are resolved
+            if status.is_conflicted() {
+                files.push(GitFileStatus {
+                    path: path.to_string(),
+                    old_path: None,
+                    status: "conflicted".to_string(),
+                    staged: false,
+                    conflict_type: conflict_types.get(path).map(|t| t.to_string()),
+                });
+                continue;
+            }
+
+            // Renamed entries are keyed by their old path; show where the file lives now
+            let index_renamed =
+                renamed_paths(entry.head_to_index()).filter(|_| status.is_index_renamed());
+            let path = index_renamed.as_ref().map_or(path, |(new, _)| new.as_str());
+
+            // Check if file is staged (in index)
+            if status.is_index_new()
+                || status.is_index_modified()
+                || status.is_index_deleted()
+                || status.is_index_renamed()
+            {
+                files.push(GitFileStatus {
+                    path: path.to_string(),
+                    old_path: index_renamed.as_ref().map(|(_, old)| old.clone()),
+                    status: format_status(status),
+                    staged: true,
+                    conflict_type: None,
+                });
+            }
+
+            // Check if file is unstaged (in working tree)
+            if status.is_wt_new()
+                || status.is_wt_modified()
+                || status.is_wt_deleted()
+                || status.is_wt_renamed()
+            {
+                let renamed =
+                    renamed_paths(entry.index_to_workdir()).filter(|_| status.is_wt_renamed());
+                files.push(GitFileStatus {
+                    path: renamed
+                        .as_ref()
+                        .map_or(path.to_string(), |(new, _)| new.clone()),
+                    old_path: renamed.map(|(_, old)| old),
+                    status: format_status(status),
+                    staged: false,
+                    conflict_type: None,
+                });
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+// Renamed entries are keyed by their old path; returns (new, old) so the file
+// is listed where it lives now
+fn renamed_paths(delta: Option<git2::DiffDelta<'_>>) -> Option<(String, String)> {
+    let delta = delta?;
+    let new_path = delta.new_file().path()?.to_str()?;
+    let old_path = delta.old_file().path()?.to_str()?;
+    Some((new_path.to_string(), old_path.to_string()))
+}
+
+// Classifies each conflict by which stages are present, like `git status` does
+fn get_conflict_types(
+    index: &git2::Index,
+) -> Result<std::collections::HashMap<String, &'static str>, GitError> {
+    let mut types = std::collections::HashMap::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let conflict_type = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+            (_, Some(_), Some(_)) => "both_modified",
+            (Some(_), Some(_), None) => "deleted_by_them",
+            (Some(_), None, Some(_)) => "deleted_by_us",
+            (None, Some(_), None) => "added_by_us",
+            (None, None, Some(_)) => "added_by_them",
+            _ => continue,
+        };
+
+        let entry = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref());
+        if let Some(entry) = entry {
+            types.insert(
+                String::from_utf8_lossy(&entry.path).to_string(),
+                conflict_type,
+            );
+        }
+    }
+
+    Ok(types)
+}
+
+fn format_status(status: git2::Status) -> String {
+    if status.is_index_new() || status.is_wt_new() {
+        "added".to_string()
+    } else if status.is_index_modified() || status.is_wt_modified() {
+        "modified".to_string()
+    } else if status.is_index_deleted() || status.is_wt_deleted() {
+        "deleted".to_string()
+    } else if status.is_index_renamed() || status.is_wt_renamed() {
+        "renamed".to_string()
+    } else {
+        "unknown".to_string()
+    }
+}
+
+pub fn get_diff(
+    repo: &Repository,
+    path: &str,
+    staged: bool,
+    rename_threshold: Option<u32>,
+) -> Result<String, GitError> {
+    if staged {
+        return diff_cached(repo, path, rename_threshold);
+    }
+
+    // Check if file is untracked
+    let statuses = repo.statuses(None)?;
+    let mut is_untracked = false;
+
+    for entry in statuses.iter() {
+        if entry.path() == Some(path) && entry.status().is_wt_new() {
+            is_untracked = true;
+            break;
+        }
+    }
+
+    // For untracked files, generate diff manually
+    if is_untracked {
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+        let file_path = workdir.join(path);
+
+        if let Ok(bytes) = std::fs::read(&file_path) {
+            if is_binary(&bytes) {
+                return Ok(format!("Binary files /dev/null and b/{} differ\n", path));
+            }
+        }
+
+        if let Ok(content) = std::fs::read_to_string(&file_path) {
+            let line_count = content.lines().count();
+            let mut diff_text = format!("@@ -0,0 +1,{} @@\n", line_count);
+            for line in content.lines() {
+                diff_text.push('+');
+                diff_text.push_str(line);
+                diff_text.push('\n');
+            }
+            return Ok(diff_text);
+        }
+    }
+
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(path);
+
+    let mut diff = repo.diff_index_to_workdir(None, Some(&mut diff_options))?;
+    if let Some(threshold) = rename_threshold {
+        find_renames(&mut diff, threshold)?;
+    }
+
+    diff_to_text(&diff)
+}
+
+pub fn get_diff_cached(repo: &Repository, path: &str) -> Result<String, GitError> {
+    diff_cached(repo, path, None)
+}
+
+fn diff_cached(
+    repo: &Repository,
+    path: &str,
+    rename_threshold: Option<u32>,
+) -> Result<String, GitError> {
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(path);
+
+    let index = repo.index()?;
+    let mut diff = match repo.head() {
+        Ok(head) => {
+            let head = head.peel_to_tree()?;
+            repo.diff_tree_to_index(Some(&head), Some(&index), Some(&mut diff_options))?
+        }
+        // Before the first commit everything staged is new, so diff against an empty index
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {
+            let empty = git2::Index::new()?;
+            repo.diff_index_to_index(&empty, &index, Some(&mut diff_options))?
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(threshold) = rename_threshold {
+        find_renames(&mut diff, threshold)?;
+    }
+
+    diff_to_text(&diff)
+}
+
+fn diff_to_text(diff: &git2::Diff) -> Result<String, GitError> {
+    let mut diff_text = String::new();
+    diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
+        let origin = line.origin();
+        let content = std::str::from_utf8(line.content()).unwrap_or("");
+
+        match origin {
+            // Binary deltas only get the "Binary files differ" notice
+            '+' | '-' if delta.flags().is_binary() => {}
+            '+' | '-' | ' ' => {
+                diff_text.push(origin);
+                diff_text.push_str(content);
+            }
+            _ => {
+                // For other lines (headers, etc), include as-is
+                diff_text.push_str(content);
+            }
+        }
+        true
+    })?;
+
+    Ok(diff_text)
+}
+
+// Staged changes against HEAD, or unstaged changes (untracked files included)
+// against the index
+fn working_diff<'a>(
+    repo: &'a Repository,
+    staged: bool,
+    diff_options: &mut git2::DiffOptions,
+) -> Result<git2::Diff<'a>, GitError> {
+    if staged {
+        let head_tree = match find_head_commit(repo)? {
+            Some(commit) => Some(commit.tree()?),
+            None => None,
+        };
+        Ok(repo.diff_tree_to_index(head_tree.as_ref(), None, Some(diff_options))?)
+    } else {
+        diff_options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        Ok(repo.diff_index_to_workdir(None, Some(diff_options))?)
+    }
+}
+
+pub fn get_diff_structured(
+    repo: &Repository,
+    path: &str,
+    staged: bool,
+) -> Result<Vec<DiffHunk>, GitError> {
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(path);
+    let diff = working_diff(repo, staged, &mut diff_options)?;
+
+    let mut hunks = Vec::new();
+    for delta_idx in 0..diff.deltas().len() {
+        // Binary files produce a patch without hunks
+        let Some(patch) = git2::Patch::from_diff(&diff, delta_idx)? else {
+            continue;
+        };
+
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx)?;
+
+            let mut lines = Vec::with_capacity(line_count);
+            for line_idx in 0..line_count {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                lines.push(DiffLine {
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    origin: line.origin(),
+                    content: std::str::from_utf8(line.content())
+                        .unwrap_or("")
+                        .to_string(),
+                });
+            }
+
+            hunks.push(DiffHunk {
+                header: String::from_utf8_lossy(hunk.header())
+                    .trim_end()
+                    .to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+    }
+
+    Ok(hunks)
+}
+
+pub fn get_diff_stat(repo: &Repository, staged: bool) -> Result<Vec<DiffStat>, GitError> {
+    let mut diff_options = git2::DiffOptions::new();
+    let diff = working_diff(repo, staged, &mut diff_options)?;
+
+    let mut stats = Vec::new();
+    for (delta_idx, delta) in diff.deltas().enumerate() {
+        let path = delta
+            .new_file()
+            .path()
+            .or(delta.old_file().path())
+            .and_then(|p| p.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        // Counts lines without collecting their content
+        let (additions, deletions) = match git2::Patch::from_diff(&diff, delta_idx)? {
+            Some(patch) => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                (additions, deletions)
+            }
+            None => (0, 0),
+        };
+
+        stats.push(DiffStat {
+            path,
+            additions,
+            deletions,
+            status: delta_status(delta.status()).to_string(),
+        });
+    }
+
+    Ok(stats)
+}
+
+pub fn get_diff_with_options(
+    repo: &Repository,
+    path: &str,
+    staged: bool,
+    opts: DiffViewOptions,
+) -> Result<DiffHunkView, GitError> {
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options
+        .pathspec(path)
+        .context_lines(opts.context_lines)
+        .ignore_whitespace(opts.ignore_whitespace)
+        .force_text(opts.word_diff);
+    let diff = working_diff(repo, staged, &mut diff_options)?;
+
+    let mut raw = String::new();
+    let mut content_lines = Vec::new();
+    diff.print(git2::DiffFormat::Patch, |delta, _hunk, line| {
+        let origin = line.origin();
+        let content = std::str::from_utf8(line.content()).unwrap_or("");
+
+        match origin {
+            '+' | '-' if delta.flags().is_binary() => {}
+            '+' | '-' | ' ' => {
+                raw.push(origin);
+                raw.push_str(content);
+                content_lines.push((origin, content.trim_end_matches('\n').to_string()));
+            }
+            _ => raw.push_str(content),
+        }
+        true
+    })?;
+
+    let word_lines = if opts.word_diff {
+        word_diff_lines(&content_lines)
+    } else {
+        Vec::new()
+    };
+
+    Ok(DiffHunkView { raw, word_lines })
+}
+
+// Above this many token comparisons a changed line is shown as a single change
+const MAX_WORD_DIFF_CELLS: usize = 250_000;
+
+// Pair each run of removed lines with the run of added lines that follows it and
+// diff the pairs word by word; unpaired lines become a single token
+fn word_diff_lines(lines: &[(char, String)]) -> Vec<Vec<WordChange>> {
+    let mut result: Vec<Vec<WordChange>> = Vec::with_capacity(lines.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        if lines[i].0 != '-' {
+            result.push(vec![WordChange {
+                kind: lines[i].0,
+                text: lines[i].1.clone(),
+            }]);
+            i += 1;
+            continue;
+        }
+
+        let removed_start = i;
+        while i < lines.len() && lines[i].0 == '-' {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].0 == '+' {
+            i += 1;
+        }
+        let removed = &lines[removed_start..added_start];
+        let added = &lines[added_start..i];
+
+        let mut removed_tokens = Vec::new();
+        let mut added_tokens = Vec::new();
+        for idx in 0..removed.len().max(added.len()) {
+            match (removed.get(idx), added.get(idx)) {
+                (Some(old), Some(new)) => {
+                    let (old_changes, new_changes) = diff_words(&old.1, &new.1);
+                    removed_tokens.push(old_changes);
+                    added_tokens.push(new_changes);
+                }
+                (Some(old), None) => removed_tokens.push(vec![WordChange {
+                    kind: '-',
+                    text: old.1.clone(),
+                }]),
+                (None, Some(new)) => added_tokens.push(vec![WordChange {
+                    kind: '+',
+                    text: new.1.clone(),
+                }]),
+                (None, None) => {}
+            }
+        }
+        result.extend(removed_tokens);
+        result.extend(added_tokens);
+    }
+
+    result
+}
+
+fn diff_words(old: &str, new: &str) -> (Vec<WordChange>, Vec<WordChange>) {
+    let old_tokens = tokenize_words(old);
+    let new_tokens = tokenize_words(new);
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+
+    if n * m > MAX_WORD_DIFF_CELLS {
+        return (
+            vec![WordChange {
+                kind: '-',
+                text: old.to_string(),
+            }],
+            vec![WordChange {
+                kind: '+',
+                text: new.to_string(),
+            }],
+        );
+    }
+
+    // Longest common subsequence table over tokens, filled from the end
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for a in (0..n).rev() {
+        for b in (0..m).rev() {
+            lcs[a][b] = if old_tokens[a] == new_tokens[b] {
+                lcs[a + 1][b + 1] + 1
+            } else {
+                lcs[a + 1][b].max(lcs[a][b + 1])
+            };
+        }
+    }
+
+    let mut old_changes = Vec::new();
+    let mut new_changes = Vec::new();
+    let (mut a, mut b) = (0, 0);
+    while a < n || b < m {
+        if a < n && b < m && old_tokens[a] == new_tokens[b] {
+            push_word_change(&mut old_changes, ' ', old_tokens[a]);
+            push_word_change(&mut new_changes, ' ', new_tokens[b]);
+            a += 1;
+            b += 1;
+        } else if b < m && (a == n || lcs[a][b + 1] >= lcs[a + 1][b]) {
+            push_word_change(&mut new_changes, '+', new_tokens[b]);
+            b += 1;
+        } else {
+            push_word_change(&mut old_changes, '-', old_tokens[a]);
+            a += 1;
+        }
+    }
+
+    (old_changes, new_changes)
+}
+
+// Merge consecutive tokens of the same kind into one change
+fn push_word_change(changes: &mut Vec<WordChange>, kind: char, text: &str) {
+    match changes.last_mut() {
+        Some(last) if last.kind == kind => last.text.push_str(text),
+        _ => changes.push(WordChange {
+            kind,
+            text: text.to_string(),
+        }),
+    }
+}
+
+// Split into runs of word characters, runs of whitespace, and single punctuation
+fn tokenize_words(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev_class = None;
+
+    for (idx, ch) in line.char_indices() {
+        let class = if ch.is_alphanumeric() || ch == '_' {
+            0
+        } else if ch.is_whitespace() {
+            1
+        } else {
+            2
+        };
+
+        if idx > start && (prev_class != Some(class) || class == 2) {
+            tokens.push(&line[start..idx]);
+            start = idx;
+        }
+        prev_class = Some(class);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+
+    tokens
+}
+
+pub fn get_index_entries(repo: &Repository) -> Result<Vec<IndexEntry>, GitError> {
+    let index = repo.index()?;
+
+    let entries = index
+        .iter()
+        .map(|entry| IndexEntry {
+            path: String::from_utf8_lossy(&entry.path).to_string(),
+            oid: entry.id.to_string(),
+            mode: entry.mode,
+            size: entry.file_size as u64,
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+pub fn ignore_file(repo: &Repository, file_path: &str) -> Result<(), GitError> {
+    add_gitignore_pattern(repo, file_path, "")
+}
+
+pub fn add_gitignore_pattern(
+    repo: &Repository,
+    pattern: &str,
+    scope_dir: &str,
+) -> Result<(), GitError> {
+    validate_gitignore_pattern(pattern)?;
+    let gitignore_path = gitignore_path(repo, scope_dir)?;
+
+    let mut content = read_gitignore(&gitignore_path)?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+
+    content.push_str(pattern);
+    content.push('\n');
+
+    if let Some(parent) = gitignore_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            GitError::Unknown(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+    std::fs::write(&gitignore_path, content)
+        .map_err(|e| GitError::Unknown(format!("Failed to write .gitignore: {}", e)))?;
+
+    Ok(())
+}
+
+pub fn remove_gitignore_pattern(
+    repo: &Repository,
+    pattern: &str,
+    scope_dir: &str,
+) -> Result<(), GitError> {
+    validate_gitignore_pattern(pattern)?;
+    let gitignore_path = gitignore_path(repo, scope_dir)?;
+
+    let content = read_gitignore(&gitignore_path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|line| *line != pattern)
+        .collect();
+    if kept.len() == lines.len() {
+        return Err(GitError::Unknown(format!(
+            "Pattern '{}' not found in {}",
+            pattern,
+            gitignore_path.display()
+        )));
+    }
+
+    let mut content = kept.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    std::fs::write(&gitignore_path, content)
+        .map_err(|e| GitError::Unknown(format!("Failed to write .gitignore: {}", e)))?;
+
+    Ok(())
+}
+
+fn validate_gitignore_pattern(pattern: &str) -> Result<(), GitError> {
+    if pattern.trim().is_empty() || pattern.contains('\n') {
+        return Err(GitError::Unknown(format!(
+            "Invalid .gitignore pattern '{}'",
+            pattern
+        )));
+    }
+    Ok(())
+}
+
+// Resolves the .gitignore for scope_dir, refusing anything that would land
+// outside the working directory
+fn gitignore_path(repo: &Repository, scope_dir: &str) -> Result<std::path::PathBuf, GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+
+    let scope = Path::new(scope_dir);
+    let escapes = scope.components().any(|component| {
+        !matches!(
+            component,
+            std::path::Component::Normal(_) | std::path::Component::CurDir
+        )
+    });
+    if escapes {
+        return Err(GitError::Unknown(format!(
+            "Directory '{}' is outside the working directory",
+            scope_dir
+        )));
+    }
+
+    Ok(workdir.join(scope).join(".gitignore"))
+}
+
+fn read_gitignore(path: &Path) -> Result<String, GitError> {
+    if !path.exists() {
+        return Ok(String::new());
+    }
+    std::fs::read_to_string(path)
+        .map_err(|e| GitError::Unknown(format!("Failed to read .gitignore: {}", e)))
+}
+
+pub fn check_ignore(repo: &Repository, file_path: &str) -> Result<bool, GitError> {
+    Ok(repo.is_path_ignored(Path::new(file_path))?)
+}
+
+pub fn get_ignored_files(repo: &Repository, dir: &str) -> Result<Vec<String>, GitError> {
+    let mut status_opts = git2::StatusOptions::new();
+    status_opts
+        .include_ignored(true)
+        .recurse_ignored_dirs(true)
+        // Needed to descend into untracked directories that hold ignored files
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+    if !dir.is_empty() {
+        status_opts.pathspec(dir);
+    }
+
+    let paths = repo
+        .statuses(Some(&mut status_opts))?
+        .iter()
+        .filter(|entry| entry.status().is_ignored())
+        .filter_map(|entry| entry.path().map(|p| p.to_string()))
+        .collect();
+
+    Ok(paths)
+}
+
+pub fn get_gitattributes(repo: &Repository) -> Result<Vec<GitAttribute>, GitError> {
+    let mut files = Vec::new();
+    if let Some(workdir) = repo.workdir() {
+        files.push(workdir.join(".gitattributes"));
+    }
+    files.push(repo.path().join("info").join("attributes"));
+
+    let mut attributes = Vec::new();
+    for file in files {
+        if !file.exists() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| GitError::Unknown(format!("Failed to read {}: {}", file.display(), e)))?;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let Some(pattern) = parts.next() else {
+                continue;
+            };
+            for attr in parts {
+                // Same spellings git uses: attr, -attr, !attr and attr=value
+                let (key, value) = if let Some((key, value)) = attr.split_once('=') {
+                    (key, value)
+                } else if let Some(key) = attr.strip_prefix('-') {
+                    (key, "unset")
+                } else if let Some(key) = attr.strip_prefix('!') {
+                    (key, "unspecified")
+                } else {
+                    (attr, "set")
+                };
+                attributes.push(GitAttribute {
+                    pattern: pattern.to_string(),
+                    key: key.to_string(),
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(attributes)
+}
+
+pub fn check_attribute(
+    repo: &Repository,
+    file_path: &str,
+    attr: &str,
+) -> Result<Option<String>, GitError> {
+    let value = repo.get_attr(Path::new(file_path), attr, git2::AttrCheckFlags::default())?;
+
+    Ok(match git2::AttrValue::from_string(value) {
+        git2::AttrValue::True => Some("set".to_string()),
+        git2::AttrValue::False => Some("unset".to_string()),
+        git2::AttrValue::String(value) => Some(value.to_string()),
+        git2::AttrValue::Bytes(value) => Some(String::from_utf8_lossy(value).to_string()),
+        git2::AttrValue::Unspecified => None,
+    })
+}
+
+pub fn stage_file(repo: &Repository, file_path: &str) -> Result<(), GitError> {
+    let mut index = repo.index()?;
+    index.add_path(std::path::Path::new(file_path))?;
+    index.write()?;
+    Ok(())
+}
+
+pub fn bulk_stage_files(repo: &Repository, file_paths: &[&str]) -> Result<(), GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+    let mut index = repo.index()?;
+
+    for file_path in file_paths {
+        // Deleted files are staged by removing them from the index
+        if workdir.join(file_path).exists() {
+            index.add_path(Path::new(file_path))?;
+        } else {
+            index.remove_path(Path::new(file_path))?;
+        }
+    }
+
+    Ok(index.write()?)
+}
+
+pub fn stage_all(repo: &Repository) -> Result<(), GitError> {
+    let mut index = repo.index()?;
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+    // add_all doesn't pick up deletions of tracked files
+    index.update_all(["*"], None)?;
+    Ok(index.write()?)
+}
+
+pub fn stage_hunk(
+    repo: &Repository,
+    _file_path: &str,
+    full_diff: &str,
+    hunk_header: &str,
+    hunk_lines: &str,
+) -> Result<(), GitError> {
+    let workdir = repo.workdir().unwrap();
+
+    // Extract the first 4 lines as diff header (like GitX does)
+    let diff_lines: Vec<&str> = full_diff.lines().collect();
+    let diff_header = diff_lines
+        .iter()
+        .take(4)
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    // Create patch: header + hunk header + hunk lines
+    let patch = format!("{}\n{}\n{}\n", diff_header, hunk_header, hunk_lines);
+
+    eprintln!("=== STAGE PATCH ===");
+    eprintln!("{}", patch);
+    eprintln!("=== END ===");
+
+    // Use git apply command with --unidiff-zero and --cached
+    apply_patch_from_stdin(
+        workdir,
+        &["--unidiff-zero", "--cached", "--ignore-whitespace"],
+        &patch,
+    )
+}
+
+// Added lines match `line_ranges` by new line number, removed lines by old;
+// ranges are inclusive
+pub fn stage_lines(
+    repo: &Repository,
+    file_path: &str,
+    line_ranges: Vec<(u32, u32)>,
+) -> Result<(), GitError> {
+    apply_selected_lines(repo, file_path, &line_ranges, false)
+}
+
+// Lines are selected the same way as for stage_lines
+pub fn discard_lines(
+    repo: &Repository,
+    file_path: &str,
+    line_ranges: Vec<(u32, u32)>,
+) -> Result<(), GitError> {
+    apply_selected_lines(repo, file_path, &line_ranges, true)
+}
+
+// Applies the selected part of a file's unstaged diff to the index, or
+// reverse-applies it to the working tree when discarding
+fn apply_selected_lines(
+    repo: &Repository,
+    file_path: &str,
+    line_ranges: &[(u32, u32)],
+    reverse: bool,
+) -> Result<(), GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Cannot apply lines in a bare repository".to_string()))?;
+
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(file_path);
+    let diff = working_diff(repo, false, &mut diff_options)?;
+
+    let mut diff_text = String::new();
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        let content = std::str::from_utf8(line.content()).unwrap_or("");
+        match line.origin() {
+            origin @ ('+' | '-' | ' ') => {
+                diff_text.push(origin);
+                diff_text.push_str(content);
+            }
+            _ => diff_text.push_str(content),
+        }
+        true
+    })?;
+
+    let patch = build_patch_for_lines(&diff_text, line_ranges, reverse);
+    if patch.is_empty() {
+        return Err(GitError::Unknown(
+            "No changes in the selected lines".to_string(),
+        ));
+    }
+
+    let args: &[&str] = if reverse {
+        &["--reverse"]
+    } else {
+        &["--cached"]
+    };
+    apply_patch_from_stdin(workdir, args, &patch)
+}
+
+// Rewrites a unified diff so only the selected changes remain. Unselected lines
+// that exist on the side the patch is applied to become context and the rest are
+// dropped: removals for staging against the index, additions when the patch is
+// reverse-applied to the working tree.
+fn build_patch_for_lines(diff: &str, ranges: &[(u32, u32)], reverse: bool) -> String {
+    let selected = |line_no: u32| {
+        ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&line_no))
+    };
+
+    let mut header = String::new();
+    let mut hunks = String::new();
+    // Net lines added by the hunks kept so far, which shifts later hunk offsets
+    let mut offset: i64 = 0;
+
+    let mut lines = diff.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some((old_start, new_start)) = parse_hunk_starts(line) else {
+            if hunks.is_empty() {
+                header.push_str(line);
+                header.push('\n');
+            }
+            continue;
+        };
+
+        let mut body = String::new();
+        let (mut old_no, mut new_no) = (old_start, new_start);
+        let (mut old_count, mut new_count) = (0i64, 0i64);
+        let mut has_changes = false;
+        // Whether the previous line made it into the patch, for "\ No newline" markers
+        let mut kept_previous = false;
+
+        while let Some(&next) = lines.peek() {
+            if next.starts_with("@@") {
+                break;
+            }
+            lines.next();
+
+            kept_previous = match next.chars().next() {
+                Some(origin @ ('+' | '-')) => {
+                    let is_addition = origin == '+';
+                    let line_no = if is_addition {
+                        &mut new_no
+                    } else {
+                        &mut old_no
+                    };
+                    let keep = selected(*line_no);
+                    *line_no += 1;
+
+                    if keep {
+                        body.push_str(next);
+                        body.push('\n');
+                        if is_addition {
+                            new_count += 1;
+                        } else {
+                            old_count += 1;
+                        }
+                        has_changes = true;
+                        true
+                    } else if is_addition == reverse {
+                        // The line stays where the patch is applied, so it becomes context
+                        body.push(' ');
+                        body.push_str(&next[1..]);
+                        body.push('\n');
+                        old_count += 1;
+                        new_count += 1;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                Some('\\') => {
+                    if kept_previous {
+                        body.push_str(next);
+                        body.push('\n');
+                    }
+                    kept_previous
+                }
+                _ => {
+                    old_no += 1;
+                    new_no += 1;
+                    body.push_str(next);
+                    body.push('\n');
+                    old_count += 1;
+                    new_count += 1;
+                    true
+                }
+            };
+        }
+
+        if !has_changes {
+            continue;
+        }
+
+        // The side the patch is applied to keeps all its lines, so its start never
+        // changes; the other side shifts by what earlier hunks added. An empty side
+        // is anchored on the line before it, as in git's own output.
+        let (fixed_start, fixed_count, other_count, shift) = if reverse {
+            (new_start, new_count, old_count, -offset)
+        } else {
+            (old_start, old_count, new_count, offset)
+        };
+        let mut other_start = i64::from(fixed_start) + shift;
+        if fixed_count == 0 {
+            other_start += 1;
+        }
+        if other_count == 0 {
+            other_start -= 1;
+        }
+        let (old_header_start, new_header_start) = if reverse {
+            (other_start, i64::from(new_start))
+        } else {
+            (i64::from(old_start), other_start)
+        };
+
+        hunks.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n{}",
+            old_header_start, old_count, new_header_start, new_count, body
+        ));
+        offset += new_count - old_count;
+    }
+
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    // A partially discarded untracked file is still there afterwards, so describe
+    // the change as a modification rather than a file creation
+    if reverse {
+        if let Some(path) = header.lines().find_map(|l| l.strip_prefix("+++ b/")) {
+            let old_header = format!("--- a/{}", path);
+            header = header
+                .lines()
+                .filter(|l| !l.starts_with("new file mode"))
+                .map(|l| {
+                    if l == "--- /dev/null" {
+                        old_header.as_str()
+                    } else {
+                        l
+                    }
+                })
+                .fold(String::new(), |acc, l| acc + l + "\n");
+        }
+    }
+
+    header + &hunks
+}
+
+// Line numbers the old and new sides of a "@@ -a,b +c,d @@" header start at
+fn parse_hunk_starts(line: &str) -> Option<(u32, u32)> {
+    let ranges = line.strip_prefix("@@ -")?;
+    let (old, rest) = ranges.split_once(" +")?;
+    let new = rest.split(' ').next()?;
+    let start = |range: &str| range.split(',').next()?.parse().ok();
+    Some((start(old)?, start(new)?))
+}
+
+pub fn unstage_file(repo: &Repository, file_path: &str) -> Result<(), GitError> {
+    let workdir = repo.workdir().unwrap();
+
+    // Use git reset HEAD <file> to unstage
+    let output = std::process::Command::new("git")
+        .args(["reset", "HEAD", file_path])
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git reset: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(format!("git reset failed: {}", stderr)));
+    }
+
+    Ok(())
+}
+
+pub fn bulk_unstage_files(repo: &Repository, file_paths: &[&str]) -> Result<(), GitError> {
+    if file_paths.is_empty() {
+        return Ok(());
+    }
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+
+    let output = std::process::Command::new("git")
+        .args(["reset", "HEAD", "--"])
+        .args(file_paths)
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git reset: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(format!("git reset failed: {}", stderr)));
+    }
+
+    Ok(())
+}
+
+pub fn unstage_all(repo: &Repository) -> Result<(), GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+
+    // Plain `git reset` also works before the first commit, unlike `git reset HEAD`
+    let output = std::process::Command::new("git")
+        .args(["reset", "--quiet"])
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git reset: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(format!("git reset failed: {}", stderr)));
+    }
+
+    Ok(())
+}
+
+pub fn unstage_hunk(
+    repo: &Repository,
+    _file_path: &str,
+    full_diff: &str,
+    hunk_header: &str,
+    hunk_lines: &str,
+) -> Result<(), GitError> {
+    let workdir = repo.workdir().unwrap();
+
+    // Extract the first 4 lines as diff header (like GitX does)
+    let diff_lines: Vec<&str> = full_diff.lines().collect();
+    let diff_header = diff_lines
+        .iter()
+        .take(4)
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    // Create patch: header + hunk header + hunk lines
+    let patch = format!("{}\n{}\n{}\n", diff_header, hunk_header, hunk_lines);
+
+    eprintln!("=== UNSTAGE PATCH ===");
     eprintln!("{}", patch);
     eprintln!("=== END ===");
 
-    // Use git apply with --cached and --reverse to unstage
-    let output = std::process::Command::new("git")
-        .args([
-            "apply",
-            "--unidiff-zero",
-            "--cached",
-            "--reverse",
-            "--ignore-whitespace",
-        ])
-        .current_dir(workdir)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .and_then(|mut child| {
-            use std::io::Write;
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(patch.as_bytes())?;
+    // Use git apply with --cached and --reverse to unstage
+    apply_patch_from_stdin(
+        workdir,
+        &[
+            "--unidiff-zero",
+            "--cached",
+            "--reverse",
+            "--ignore-whitespace",
+        ],
+        &patch,
+    )
+}
+
+pub fn discard_file(repo: &Repository, file_path: &str) -> Result<(), GitError> {
+    let workdir = repo.workdir().unwrap();
+
+    // Use git checkout -- <file> to discard changes
+    let output = std::process::Command::new("git")
+        .args(["checkout", "--", file_path])
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git checkout: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(format!(
+            "git checkout failed: {}",
+            stderr
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn discard_all(repo: &Repository, remove_untracked: bool) -> Result<(), GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+
+    if remove_untracked {
+        let mut status_opts = git2::StatusOptions::new();
+        status_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(false);
+
+        for entry in repo.statuses(Some(&mut status_opts))?.iter() {
+            if !entry.status().is_wt_new() {
+                continue;
+            }
+            let Some(path) = entry.path() else {
+                continue;
+            };
+
+            // Untracked directories are reported once, with a trailing slash
+            let full_path = workdir.join(path);
+            let result = if full_path.is_dir() {
+                std::fs::remove_dir_all(&full_path)
+            } else {
+                std::fs::remove_file(&full_path)
+            };
+            result.map_err(|e| GitError::Unknown(format!("Failed to remove {}: {}", path, e)))?;
+        }
+    }
+
+    Ok(repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?)
+}
+
+pub fn discard_hunk(
+    repo: &Repository,
+    _file_path: &str,
+    full_diff: &str,
+    hunk_header: &str,
+    hunk_lines: &str,
+) -> Result<(), GitError> {
+    let workdir = repo.workdir().unwrap();
+
+    let diff_lines: Vec<&str> = full_diff.lines().collect();
+    let diff_header = diff_lines
+        .iter()
+        .take(4)
+        .cloned()
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    let patch = format!("{}\n{}\n{}\n", diff_header, hunk_header, hunk_lines);
+
+    // Use git apply --reverse (without --cached) to discard working tree changes
+    apply_patch_from_stdin(
+        workdir,
+        &["--unidiff-zero", "--reverse", "--ignore-whitespace"],
+        &patch,
+    )
+}
+
+// Feed a patch to `git apply` on stdin
+fn apply_patch_from_stdin(workdir: &Path, args: &[&str], patch: &str) -> Result<(), GitError> {
+    let output = std::process::Command::new("git")
+        .arg("apply")
+        .args(args)
+        .current_dir(workdir)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(patch.as_bytes())?;
+            }
+            child.wait_with_output()
+        })
+        .map_err(|e| GitError::Unknown(format!("Failed to run git apply: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(format!("git apply failed: {}", stderr)));
+    }
+
+    Ok(())
+}
+
+fn find_head_commit(repo: &Repository) -> Result<Option<git2::Commit<'_>>, GitError> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        // Unborn branch (no commits yet)
+        Err(e)
+            if e.code() == git2::ErrorCode::UnbornBranch
+                || e.code() == git2::ErrorCode::NotFound =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn resolve_signature(
+    repo: &Repository,
+    name: Option<&str>,
+    email: Option<&str>,
+) -> Result<git2::Signature<'static>, GitError> {
+    if name.is_none() && email.is_none() {
+        return Ok(repo.signature()?);
+    }
+
+    // Fill in whichever half is missing from user.name / user.email
+    let config = repo.config()?;
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => config.get_string("user.name")?,
+    };
+    let email = match email {
+        Some(email) => email.to_string(),
+        None => config.get_string("user.email")?,
+    };
+
+    Ok(git2::Signature::now(&name, &email)?)
+}
+
+pub fn commit_changes(
+    repo: &Repository,
+    message: &str,
+    author_name: Option<&str>,
+    author_email: Option<&str>,
+) -> Result<String, GitError> {
+    let author = resolve_signature(repo, author_name, author_email)?;
+    let committer = repo.signature().unwrap_or_else(|_| author.clone());
+
+    let mut index = repo.index()?;
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+
+    // Initial commit has no parents
+    let parent = find_head_commit(repo)?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    let oid = repo.commit(Some("HEAD"), &author, &committer, message, &tree, &parents)?;
+
+    Ok(oid.to_string())
+}
+
+pub fn amend_commit(
+    repo: &Repository,
+    new_message: Option<&str>,
+    author: Option<GitSignature>,
+    reset_author: bool,
+) -> Result<String, GitError> {
+    let head_commit = find_head_commit(repo)?
+        .ok_or_else(|| GitError::Unknown("There is no commit to amend".to_string()))?;
+
+    let author = match author {
+        Some(sig) => Some(git2::Signature::now(&sig.name, &sig.email)?),
+        None if reset_author => Some(repo.signature()?),
+        None => None,
+    };
+    let committer = repo.signature()?;
+
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    // Updating "HEAD" moves the current branch, or HEAD itself when detached
+    let oid = head_commit.amend(
+        Some("HEAD"),
+        author.as_ref(),
+        Some(&committer),
+        None,
+        new_message,
+        Some(&tree),
+    )?;
+
+    Ok(oid.to_string())
+}
+
+pub fn squash_commits(
+    repo: &Repository,
+    base_commit: &str,
+    message: Option<&str>,
+) -> Result<String, GitError> {
+    let head_commit = find_head_commit(repo)?
+        .ok_or_else(|| GitError::Unknown("There are no commits to squash".to_string()))?;
+    let base = repo.revparse_single(base_commit)?.peel_to_commit()?;
+
+    if !repo.graph_descendant_of(head_commit.id(), base.id())? {
+        return Err(GitError::Unknown(format!(
+            "{} is not an ancestor of HEAD",
+            base_commit
+        )));
+    }
+
+    // Oldest first, like the todo list of an interactive rebase
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head_commit.id())?;
+    revwalk.hide(base.id())?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    let squashed = revwalk
+        .map(|oid| repo.find_commit(oid?))
+        .collect::<Result<Vec<_>, git2::Error>>()?;
+
+    let message = match message {
+        Some(message) => message.to_string(),
+        None => squashed
+            .iter()
+            .filter_map(|c| c.message())
+            .map(|m| m.trim())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+    };
+
+    // The squashed commit keeps the original author of the oldest commit
+    let author = squashed[0].author();
+    let committer = repo.signature()?;
+
+    repo.reset(base.as_object(), git2::ResetType::Soft, None)?;
+
+    // Commit the old HEAD tree so anything staged on top isn't swept into the squash
+    let tree = head_commit.tree()?;
+    let oid = repo.commit(Some("HEAD"), &author, &committer, &message, &tree, &[&base])?;
+
+    Ok(oid.to_string())
+}
+
+// Hard also resets the working tree, discarding uncommitted changes
+pub fn reset_to_commit(
+    repo: &Repository,
+    commit_id: &str,
+    mode: ResetMode,
+) -> Result<(), GitError> {
+    let target = repo.revparse_single(commit_id)?.peel_to_commit()?;
+
+    let (reset_type, mode_name) = match mode {
+        ResetMode::Soft => (git2::ResetType::Soft, "soft"),
+        ResetMode::Mixed => (git2::ResetType::Mixed, "mixed"),
+        ResetMode::Hard => (git2::ResetType::Hard, "hard"),
+    };
+
+    repo.reset(target.as_object(), reset_type, None)
+        .map_err(|e| {
+            GitError::Unknown(format!(
+                "Failed to {} reset to {}: {}",
+                mode_name,
+                &target.id().to_string()[..7],
+                e.message()
+            ))
+        })
+}
+
+pub fn checkout_branch(repo: &Repository, branch_name: &str) -> Result<(), GitError> {
+    let workdir = repo.workdir().unwrap();
+
+    // Use git checkout with error handling
+    let output = std::process::Command::new("git")
+        .args(["checkout", branch_name])
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git checkout: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn fetch_remote(
+    repo: &Repository,
+    remote_name: &str,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    let mut remote = repo.find_remote(remote_name)?;
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(create_remote_callbacks(auth));
+
+    // An empty refspec list uses the remote's configured fetch refspecs
+    Ok(remote.fetch(&[] as &[&str], Some(&mut fetch_options), None)?)
+}
+
+pub fn fetch_all_remotes(repo: &Repository, auth: &AuthConfig) -> Result<Vec<String>, GitError> {
+    let mut fetched = Vec::new();
+    let mut failures = Vec::new();
+
+    for name in repo.remotes()?.iter().flatten() {
+        match fetch_remote(repo, name, auth) {
+            Ok(()) => fetched.push(name.to_string()),
+            Err(e) => failures.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    // Partial success is reported through the returned names
+    if fetched.is_empty() && !failures.is_empty() {
+        return Err(GitError::Unknown(format!(
+            "Failed to fetch remotes: {}",
+            failures.join("; ")
+        )));
+    }
+
+    Ok(fetched)
+}
+
+pub fn prune_remote(repo: &Repository, remote_name: &str) -> Result<Vec<String>, GitError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+
+    let output = std::process::Command::new("git")
+        .args(["remote", "prune", remote_name])
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git remote prune: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
+
+    // Pruned refs are reported as " * [pruned] origin/branch"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let pruned = stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("* [pruned] "))
+        .map(|name| name.to_string())
+        .collect();
+
+    Ok(pruned)
+}
+
+pub fn pull_remote(
+    repo: &Repository,
+    remote_name: &str,
+    rebase: bool,
+    ff_only: bool,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    if rebase && ff_only {
+        return Err(GitError::Unknown(
+            "Cannot pull with both rebase and fast-forward only".to_string(),
+        ));
+    }
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitError::Unknown("Repository has no working directory".to_string()))?;
+
+    let head = repo.head()?;
+    let branch_name = head
+        .shorthand()
+        .filter(|_| head.is_branch())
+        .ok_or_else(|| GitError::Unknown("Cannot pull with a detached HEAD".to_string()))?;
+
+    // Fetch through libgit2 so the configured credentials are used
+    fetch_remote(repo, remote_name, auth)?;
+
+    // Integrate the branch's upstream when it lives on this remote, otherwise
+    // the remote branch of the same name
+    let config = repo.config()?;
+    let merge_branch = match config.get_string(&format!("branch.{}.remote", branch_name)) {
+        Ok(remote) if remote == remote_name => config
+            .get_string(&format!("branch.{}.merge", branch_name))
+            .ok()
+            .map(|merge| merge.trim_start_matches("refs/heads/").to_string()),
+        _ => None,
+    }
+    .unwrap_or_else(|| branch_name.to_string());
+
+    let target = format!("{}/{}", remote_name, merge_branch);
+    if repo
+        .find_reference(&format!("refs/remotes/{}", target))
+        .is_err()
+    {
+        return Err(GitError::BranchNotFound(target));
+    }
+
+    let args: &[&str] = if rebase {
+        &["rebase", &target]
+    } else if ff_only {
+        &["merge", "--ff-only", &target]
+    } else {
+        &["merge", "--no-edit", &target]
+    };
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(workdir)
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git {}: {}", args[0], e)))?;
+
+    if !output.status.success() {
+        // git wrote the conflicts to the index on disk, so reload it
+        let mut index = repo.index()?;
+        index.read(true)?;
+        if index.has_conflicts() {
+            return Err(GitError::MergeConflict(conflicted_paths(&index)?));
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if ff_only && stderr.contains("Not possible to fast-forward") {
+            return Err(GitError::Unknown(
+                "Cannot fast-forward: the current branch has diverged from the remote".to_string(),
+            ));
+        }
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
+
+    Ok(())
+}
+
+pub fn push_remote(
+    repo: &Repository,
+    remote_name: &str,
+    branch_name: Option<&str>,
+    force: bool,
+    force_with_lease: bool,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    // Default to the currently checked out branch
+    let branch = match branch_name {
+        Some(name) => name.to_string(),
+        None => {
+            if repo.head_detached()? {
+                return Err(GitError::Unknown(
+                    "Cannot push from a detached HEAD".to_string(),
+                ));
+            }
+            repo.head()?
+                .shorthand()
+                .ok_or_else(|| {
+                    GitError::Unknown("Current branch name is not valid UTF-8".to_string())
+                })?
+                .to_string()
+        }
+    };
+
+    let ref_name = format!("refs/heads/{}", branch);
+    let mut remote = repo.find_remote(remote_name)?;
+
+    if force_with_lease {
+        check_push_lease(repo, &mut remote, remote_name, &branch, &ref_name, auth)?;
+    }
+
+    let refspec = if force || force_with_lease {
+        format!("+{}:{}", ref_name, ref_name)
+    } else {
+        format!("{}:{}", ref_name, ref_name)
+    };
+
+    push_refspec(&mut remote, &refspec, auth)
+}
+
+pub fn push_tag(
+    repo: &Repository,
+    remote_name: &str,
+    tag_name: &str,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    let ref_name = format!("refs/tags/{}", tag_name);
+    repo.find_reference(&ref_name)?;
+
+    let mut remote = repo.find_remote(remote_name)?;
+    push_refspec(&mut remote, &format!("{}:{}", ref_name, ref_name), auth)
+}
+
+pub fn delete_remote_tag(
+    repo: &Repository,
+    remote_name: &str,
+    tag_name: &str,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    let mut remote = repo.find_remote(remote_name)?;
+    // An empty source deletes the destination ref on the remote
+    push_refspec(&mut remote, &format!(":refs/tags/{}", tag_name), auth)
+}
+
+// Push a single refspec, turning per-ref rejections from the server into an error
+fn push_refspec(
+    remote: &mut git2::Remote,
+    refspec: &str,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    let mut rejected = Vec::new();
+    {
+        let mut callbacks = create_remote_callbacks(auth);
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(message) = status {
+                rejected.push(format!("{} ({})", refname, message));
+            }
+            Ok(())
+        });
+
+        let mut push_options = git2::PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+
+        remote
+            .push(&[refspec], Some(&mut push_options))
+            .map_err(map_push_error)?;
+    }
+
+    if !rejected.is_empty() {
+        return Err(GitError::PushRejected(rejected));
+    }
+
+    Ok(())
+}
+
+// Refuse to overwrite the remote branch if it moved since our last fetch
+fn check_push_lease(
+    repo: &Repository,
+    remote: &mut git2::Remote,
+    remote_name: &str,
+    branch: &str,
+    ref_name: &str,
+    auth: &AuthConfig,
+) -> Result<(), GitError> {
+    let expected = repo
+        .find_reference(&format!("refs/remotes/{}/{}", remote_name, branch))
+        .ok()
+        .and_then(|r| r.target());
+
+    let connection = remote
+        .connect_auth(
+            git2::Direction::Push,
+            Some(create_remote_callbacks(auth)),
+            None,
+        )
+        .map_err(map_push_error)?;
+    let actual = connection
+        .list()?
+        .iter()
+        .find(|head| head.name() == ref_name)
+        .map(|head| head.oid());
+    drop(connection);
+
+    if actual.is_some() && actual != expected {
+        return Err(GitError::PushRejected(vec![format!(
+            "{} on '{}' has changed since the last fetch (force-with-lease)",
+            branch, remote_name
+        )]));
+    }
+
+    Ok(())
+}
+
+fn map_push_error(e: git2::Error) -> GitError {
+    match e.code() {
+        git2::ErrorCode::NotFastForward => GitError::PushRejected(vec![e.message().to_string()]),
+        _ => e.into(),
+    }
+}
+
+fn conflicted_paths(index: &git2::Index) -> Result<Vec<String>, GitError> {
+    let mut paths = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        // Any side of the conflict carries the path
+        let entry = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref());
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).to_string());
+        }
+    }
+
+    Ok(paths)
+}
+
+pub fn get_conflict_files(repo: &Repository) -> Result<Vec<ConflictEntry>, GitError> {
+    let index = repo.index()?;
+    let mut entries = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref());
+        if let Some(entry) = entry {
+            entries.push(ConflictEntry {
+                path: String::from_utf8_lossy(&entry.path).to_string(),
+                our_oid: conflict.our.as_ref().map(|e| e.id.to_string()),
+                their_oid: conflict.their.as_ref().map(|e| e.id.to_string()),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+pub fn get_conflict_versions(repo: &Repository, path: &str) -> Result<ConflictVersions, GitError> {
+    let index = repo.index()?;
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let matches_path = [&conflict.ancestor, &conflict.our, &conflict.their]
+            .iter()
+            .any(|entry| entry.as_ref().is_some_and(|e| e.path == path.as_bytes()));
+        if !matches_path {
+            continue;
+        }
+
+        // Binary or non-UTF-8 content can't be shown in a text merge view
+        let content = |entry: &Option<git2::IndexEntry>| -> Result<Option<String>, GitError> {
+            let Some(entry) = entry else {
+                return Ok(None);
+            };
+            let blob = repo.find_blob(entry.id)?;
+            if is_binary(blob.content()) {
+                return Ok(None);
+            }
+            Ok(std::str::from_utf8(blob.content())
+                .ok()
+                .map(|s| s.to_string()))
+        };
+
+        return Ok(ConflictVersions {
+            base: content(&conflict.ancestor)?,
+            ours: content(&conflict.our)?,
+            theirs: content(&conflict.their)?,
+        });
+    }
+
+    Err(GitError::Unknown(format!("{} is not in conflict", path)))
+}
+
+pub fn mark_conflict_resolved(repo: &Repository, path: &str) -> Result<(), GitError> {
+    let workdir = repo.workdir().ok_or_else(|| {
+        GitError::Unknown("Cannot resolve conflicts in a bare repository".to_string())
+    })?;
+    let mut index = repo.index()?;
+
+    // Staging the file (or its deletion) clears the conflict entries for it
+    if workdir.join(path).exists() {
+        index.add_path(Path::new(path))?;
+    } else {
+        index.remove_path(Path::new(path))?;
+    }
+    Ok(index.write()?)
+}
+
+pub fn merge_branch(
+    repo: &Repository,
+    branch_name: &str,
+    no_ff: bool,
+    squash: bool,
+) -> Result<MergeResult, GitError> {
+    let reference = repo.resolve_reference_from_short_name(branch_name)?;
+    let their_commit = repo.reference_to_annotated_commit(&reference)?;
+    let (analysis, _) = repo.merge_analysis(&[&their_commit])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(MergeResult {
+            fast_forwarded: false,
+            conflicts: Vec::new(),
+            merged_commit: None,
+        });
+    }
+
+    // Fast-forward by moving the current branch to the target commit
+    if analysis.is_fast_forward() && !no_ff && !squash {
+        let target = repo.find_commit(their_commit.id())?;
+        repo.checkout_tree(
+            target.as_object(),
+            Some(git2::build::CheckoutBuilder::new().safe()),
+        )?;
+
+        let mut head = repo.head()?;
+        head.set_target(target.id(), &format!("merge {}: Fast-forward", branch_name))?;
+
+        return Ok(MergeResult {
+            fast_forwarded: true,
+            conflicts: Vec::new(),
+            merged_commit: Some(target.id().to_string()),
+        });
+    }
+
+    if analysis.is_unborn() {
+        return Err(GitError::Unknown(
+            "Cannot merge into a branch with no commits".to_string(),
+        ));
+    }
+
+    repo.merge(
+        &[&their_commit],
+        None,
+        Some(git2::build::CheckoutBuilder::new().safe()),
+    )?;
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        let conflicts = conflicted_paths(&index)?;
+        if squash {
+            // A squash merge never records MERGE_HEAD
+            repo.cleanup_state()?;
+        }
+        return Ok(MergeResult {
+            fast_forwarded: false,
+            conflicts,
+            merged_commit: None,
+        });
+    }
+
+    // Squash leaves the result staged for the user to commit
+    if squash {
+        repo.cleanup_state()?;
+        return Ok(MergeResult {
+            fast_forwarded: false,
+            conflicts: Vec::new(),
+            merged_commit: None,
+        });
+    }
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let merged = repo.find_commit(their_commit.id())?;
+    let signature = repo.signature()?;
+    let message = repo
+        .message()
+        .unwrap_or_else(|_| format!("Merge branch '{}'", branch_name));
+
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head_commit, &merged],
+    )?;
+    repo.cleanup_state()?;
+
+    Ok(MergeResult {
+        fast_forwarded: false,
+        conflicts: Vec::new(),
+        merged_commit: Some(oid.to_string()),
+    })
+}
+
+pub fn is_merge_in_progress(repo: &Repository) -> bool {
+    repo.state() == git2::RepositoryState::Merge
+}
+
+pub fn get_merge_message(repo: &Repository) -> Result<Option<String>, GitError> {
+    // MERGE_MSG lives in the .git directory, not the working tree
+    let merge_msg_path = repo.path().join("MERGE_MSG");
+    if !merge_msg_path.exists() {
+        return Ok(None);
+    }
+
+    std::fs::read_to_string(&merge_msg_path)
+        .map(Some)
+        .map_err(|e| GitError::Unknown(format!("Failed to read MERGE_MSG: {}", e)))
+}
+
+pub fn abort_merge(repo: &Repository) -> Result<(), GitError> {
+    if !is_merge_in_progress(repo) {
+        return Err(GitError::Unknown("No merge in progress".to_string()));
+    }
+
+    repo.cleanup_state()?;
+    reset_hard_to_head(repo)
+}
+
+// Restore index and working tree to HEAD, clearing any conflict entries
+fn reset_hard_to_head(repo: &Repository) -> Result<(), GitError> {
+    let head = repo.head()?.peel_to_commit()?;
+    Ok(repo.reset(head.as_object(), git2::ResetType::Hard, None)?)
+}
+
+pub fn is_rebase_in_progress(repo: &Repository) -> bool {
+    matches!(
+        repo.state(),
+        git2::RepositoryState::Rebase
+            | git2::RepositoryState::RebaseInteractive
+            | git2::RepositoryState::RebaseMerge
+    )
+}
+
+pub fn start_rebase(repo: &Repository, branch: &str, onto: &str) -> Result<RebaseStatus, GitError> {
+    // Rebase the branch ref itself so it is updated when the rebase finishes
+    let branch_ref = repo.resolve_reference_from_short_name(branch)?;
+    let branch_commit = repo.reference_to_annotated_commit(&branch_ref)?;
+    let onto_oid = repo.revparse_single(onto)?.peel_to_commit()?.id();
+    let onto_commit = repo.find_annotated_commit(onto_oid)?;
+
+    let mut rebase = repo.rebase(Some(&branch_commit), Some(&onto_commit), None, None)?;
+    run_rebase(repo, &mut rebase)
+}
+
+pub fn continue_rebase(repo: &Repository, commit_message: &str) -> Result<RebaseStatus, GitError> {
+    let mut rebase = repo.open_rebase(None)?;
+
+    let index = repo.index()?;
+    if index.has_conflicts() {
+        return Ok(RebaseStatus {
+            completed: false,
+            conflicts: conflicted_paths(&index)?,
+            current_step: rebase.operation_current().map_or(0, |i| i + 1),
+            total_steps: rebase.len(),
+        });
+    }
+
+    // Commit the step the user just resolved; an empty message keeps the original
+    let message = Some(commit_message).filter(|m| !m.is_empty());
+    commit_rebase_step(&mut rebase, &repo.signature()?, message)?;
+
+    run_rebase(repo, &mut rebase)
+}
+
+pub fn skip_rebase_step(repo: &Repository) -> Result<RebaseStatus, GitError> {
+    let mut rebase = repo.open_rebase(None)?;
+
+    // Throw away the current step's changes before moving on
+    reset_hard_to_head(repo)?;
+
+    run_rebase(repo, &mut rebase)
+}
+
+pub fn abort_rebase(repo: &Repository) -> Result<(), GitError> {
+    Ok(repo.open_rebase(None)?.abort()?)
+}
+
+fn run_rebase(repo: &Repository, rebase: &mut git2::Rebase) -> Result<RebaseStatus, GitError> {
+    let signature = repo.signature()?;
+    let total_steps = rebase.len();
+
+    while let Some(operation) = rebase.next() {
+        operation?;
+
+        // Stop on conflicts so the user can resolve them and continue
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            return Ok(RebaseStatus {
+                completed: false,
+                conflicts: conflicted_paths(&index)?,
+                current_step: rebase.operation_current().map_or(0, |i| i + 1),
+                total_steps,
+            });
+        }
+
+        commit_rebase_step(rebase, &signature, None)?;
+    }
+
+    rebase.finish(Some(&signature))?;
+
+    Ok(RebaseStatus {
+        completed: true,
+        conflicts: Vec::new(),
+        current_step: total_steps,
+        total_steps,
+    })
+}
+
+fn commit_rebase_step(
+    rebase: &mut git2::Rebase,
+    signature: &git2::Signature,
+    message: Option<&str>,
+) -> Result<(), GitError> {
+    match rebase.commit(None, signature, message) {
+        Ok(_) => Ok(()),
+        // Patch is already upstream, nothing to commit for this step
+        Err(e) if e.code() == git2::ErrorCode::Applied => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn cherry_pick_commit(
+    repo: &Repository,
+    commit_id: &str,
+    mainline: Option<usize>,
+) -> Result<CherryPickResult, GitError> {
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+
+    let mut options = git2::CherrypickOptions::new();
+    if let Some(mainline) = mainline {
+        options.mainline(mainline as u32);
+    }
+    repo.cherrypick(&commit, Some(&mut options))?;
+
+    let index = repo.index()?;
+    if index.has_conflicts() {
+        return Ok(CherryPickResult {
+            conflicts: conflicted_paths(&index)?,
+            needs_commit: true,
+        });
+    }
+
+    // Clean pick: commit it keeping the original author and message
+    commit_index_on_head(repo, &commit.author(), commit.message().unwrap_or(""))?;
+    repo.cleanup_state()?;
+
+    Ok(CherryPickResult {
+        conflicts: Vec::new(),
+        needs_commit: false,
+    })
+}
+
+// Picks from..to oldest first, like `git cherry-pick from..to`
+pub fn cherry_pick_range(
+    repo: &Repository,
+    from: &str,
+    to: &str,
+) -> Result<CherryPickRangeResult, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(repo.revparse_single(to)?.peel_to_commit()?.id())?;
+    revwalk.hide(repo.revparse_single(from)?.peel_to_commit()?.id())?;
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    let commits = revwalk
+        .map(|oid| repo.find_commit(oid?))
+        .collect::<Result<Vec<_>, git2::Error>>()?;
+
+    // Check up front so we don't stop halfway through on a merge
+    if let Some(merge) = commits.iter().find(|c| c.parent_count() > 1) {
+        return Err(GitError::Unknown(format!(
+            "Cannot cherry-pick merge commit {} as part of a range",
+            merge.id()
+        )));
+    }
+
+    let mut picked = Vec::new();
+    for commit in commits {
+        let commit_id = commit.id().to_string();
+        let result = cherry_pick_commit(repo, &commit_id, None)?;
+        if result.needs_commit {
+            return Ok(CherryPickRangeResult {
+                picked,
+                conflicts_at: Some(commit_id),
+            });
+        }
+        picked.push(commit_id);
+    }
+
+    Ok(CherryPickRangeResult {
+        picked,
+        conflicts_at: None,
+    })
+}
+
+pub fn abort_cherry_pick(repo: &Repository) -> Result<(), GitError> {
+    if repo.state() != git2::RepositoryState::CherryPick {
+        return Err(GitError::Unknown("No cherry-pick in progress".to_string()));
+    }
+
+    repo.cleanup_state()?;
+    reset_hard_to_head(repo)
+}
+
+pub fn revert_commit(
+    repo: &Repository,
+    commit_id: &str,
+    mainline: Option<usize>,
+    auto_commit: bool,
+) -> Result<RevertResult, GitError> {
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+
+    let mut options = git2::RevertOptions::new();
+    if let Some(mainline) = mainline {
+        options.mainline(mainline as u32);
+    }
+    repo.revert(&commit, Some(&mut options))?;
+
+    let index = repo.index()?;
+    if index.has_conflicts() {
+        return Ok(RevertResult {
+            conflicts: conflicted_paths(&index)?,
+            reverted_commit: None,
+        });
+    }
+
+    if !auto_commit {
+        return Ok(RevertResult {
+            conflicts: Vec::new(),
+            reverted_commit: None,
+        });
+    }
+
+    // libgit2 prepares the standard "Revert ..." message in MERGE_MSG
+    let message = repo.message().unwrap_or_else(|_| {
+        format!(
+            "Revert \"{}\"\n\nThis reverts commit {}.\n",
+            commit.summary().unwrap_or(""),
+            commit.id()
+        )
+    });
+    let signature = repo.signature()?;
+    let new_oid = commit_index_on_head(repo, &signature, &message)?;
+    repo.cleanup_state()?;
+
+    Ok(RevertResult {
+        conflicts: Vec::new(),
+        reverted_commit: Some(new_oid.to_string()),
+    })
+}
+
+fn commit_index_on_head(
+    repo: &Repository,
+    author: &git2::Signature,
+    message: &str,
+) -> Result<git2::Oid, GitError> {
+    let mut index = repo.index()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let committer = repo.signature()?;
+
+    Ok(repo.commit(Some("HEAD"), author, &committer, message, &tree, &[&head])?)
+}
+
+pub fn create_stash(
+    repo: &mut Repository,
+    message: Option<&str>,
+    include_untracked: bool,
+    keep_index: bool,
+) -> Result<Option<git2::Oid>, GitError> {
+    let signature = repo.signature()?;
+
+    let mut flags = git2::StashFlags::DEFAULT;
+    if include_untracked {
+        flags |= git2::StashFlags::INCLUDE_UNTRACKED;
+    }
+    if keep_index {
+        flags |= git2::StashFlags::KEEP_INDEX;
+    }
+
+    match repo.stash_save2(&signature, message, Some(flags)) {
+        Ok(oid) => Ok(Some(oid)),
+        // Nothing to stash
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn list_stashes(repo: &mut Repository) -> Result<Vec<GitStash>, GitError> {
+    let mut stashes = Vec::new();
+
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push(GitStash {
+            index,
+            message: message.to_string(),
+            id: oid.to_string(),
+        });
+        true
+    })?;
+
+    Ok(stashes)
+}
+
+pub fn apply_stash(
+    repo: &mut Repository,
+    index: usize,
+    reinstate_index: bool,
+) -> Result<(), GitError> {
+    let mut options = git2::StashApplyOptions::new();
+    if reinstate_index {
+        options.reinstantiate_index();
+    }
+    Ok(repo.stash_apply(index, Some(&mut options))?)
+}
+
+pub fn pop_stash(repo: &mut Repository, index: usize) -> Result<(), GitError> {
+    Ok(repo.stash_pop(index, None)?)
+}
+
+pub fn drop_stash(repo: &mut Repository, index: usize) -> Result<(), GitError> {
+    Ok(repo.stash_drop(index)?)
+}
+
+pub fn get_stash_diff(repo: &mut Repository, index: usize) -> Result<Vec<CommitFile>, GitError> {
+    let stash = list_stashes(repo)?
+        .into_iter()
+        .find(|stash| stash.index == index)
+        .ok_or_else(|| GitError::Unknown(format!("Stash@{{{}}} not found", index)))?;
+
+    // A stash commit's first parent is the HEAD it was created on
+    get_commit_diff(repo, &stash.id, None)
+}
+
+pub fn get_reflog(repo: &Repository, ref_name: &str) -> Result<Vec<ReflogEntry>, GitError> {
+    if repo.is_bare() {
+        return Err(GitError::Unknown(
+            "Reflog is not available in a bare repository".to_string(),
+        ));
+    }
+
+    let reflog = repo.reflog(ref_name)?;
+    let entries = reflog
+        .iter()
+        .map(|entry| {
+            let committer = entry.committer();
+            ReflogEntry {
+                id_old: entry.id_old().to_string(),
+                id_new: entry.id_new().to_string(),
+                message: entry.message().unwrap_or("").to_string(),
+                author: committer.name().unwrap_or("").to_string(),
+                timestamp: committer.when().seconds().to_string(),
+            }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+pub fn get_commits(
+    repo: &Repository,
+    limit: usize,
+    local_only: bool,
+    branch_name: Option<&str>,
+    after: Option<&str>,
+    first_parent_only: bool,
+) -> Result<CommitPage, GitError> {
+    let mut revwalk = repo.revwalk()?;
+
+    push_commit_sources(repo, &mut revwalk, local_only, branch_name)?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+    if first_parent_only {
+        revwalk.simplify_first_parent()?;
+    }
+
+    let (branch_map, tag_map) = get_ref_maps(repo)?;
+
+    let after_oid = after
+        .map(|id| Ok::<_, GitError>(repo.revparse_single(id)?.peel_to_commit()?.id()))
+        .transpose()?;
+
+    // Index into `commits` where the requested page begins
+    let mut page_start = if after_oid.is_none() { Some(0) } else { None };
+    let mut has_more = false;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let oid = oid?;
+
+        if let Some(start) = page_start {
+            if commits.len() - start >= limit {
+                has_more = true;
+                break;
+            }
+        }
+
+        let commit = repo.find_commit(oid)?;
+        commits.push(to_git_commit(&commit, &branch_map, &tag_map));
+
+        if page_start.is_none() && Some(oid) == after_oid {
+            page_start = Some(commits.len());
+        }
+    }
+
+    let page_start = page_start.ok_or_else(|| {
+        GitError::Unknown(format!(
+            "Commit {} not found in history",
+            after.unwrap_or("")
+        ))
+    })?;
+
+    // Lanes are laid out from the top of history so pages line up when appended
+    if first_parent_only {
+        // Merged-in parents are never walked, so leave them out of the layout or
+        // they would open lanes that never close
+        let merged_parents: Vec<Vec<String>> = commits
+            .iter_mut()
+            .map(|c| c.parents.split_off(c.parents.len().min(1)))
+            .collect();
+        graph::calculate_lanes(&mut commits);
+        for (commit, merged) in commits.iter_mut().zip(merged_parents) {
+            commit.parents.extend(merged);
+        }
+    } else {
+        graph::calculate_lanes(&mut commits);
+    }
+
+    Ok(CommitPage {
+        commits: commits.split_off(page_start),
+        has_more,
+    })
+}
+
+// Push the tips the history walk should start from: a single branch if one is
+// named, otherwise every local (or local and remote) branch
+fn push_commit_sources(
+    repo: &Repository,
+    revwalk: &mut git2::Revwalk,
+    local_only: bool,
+    branch_name: Option<&str>,
+) -> Result<(), GitError> {
+    // If specific branch requested, only walk from that branch
+    if let Some(branch) = branch_name {
+        // Try local branch first
+        if let Ok(b) = repo.find_branch(branch, git2::BranchType::Local) {
+            if let Some(target) = b.get().target() {
+                revwalk.push(target)?;
+            }
+        } else if let Ok(b) = repo.find_branch(branch, git2::BranchType::Remote) {
+            // Try remote branch
+            if let Some(target) = b.get().target() {
+                revwalk.push(target)?;
+            }
+        } else {
+            // Try as reference
+            let ref_name = format!("refs/remotes/{}", branch);
+            if let Ok(reference) = repo.find_reference(&ref_name) {
+                if let Some(target) = reference.target() {
+                    revwalk.push(target)?;
+                }
+            }
+        }
+    } else {
+        // Push branches based on filter
+        let branch_type = if local_only {
+            Some(git2::BranchType::Local)
+        } else {
+            None // All branches
+        };
+
+        for branch in repo.branches(branch_type)? {
+            let (branch, _) = branch?;
+            if let Some(target) = branch.get().target() {
+                revwalk.push(target)?;
             }
-            child.wait_with_output()
+        }
+    }
+
+    Ok(())
+}
+
+pub fn get_commits_by_author(
+    repo: &Repository,
+    author: &str,
+    limit: usize,
+    branch_name: Option<&str>,
+) -> Result<Vec<GitCommit>, GitError> {
+    let needle = author.to_lowercase();
+    collect_matching_commits(repo, branch_name, limit, |commit| {
+        let author = commit.author();
+        contains_ignore_case(author.name(), &needle)
+            || contains_ignore_case(author.email(), &needle)
+    })
+}
+
+pub fn get_commits_by_date_range(
+    repo: &Repository,
+    from_ts: i64,
+    to_ts: i64,
+    limit: usize,
+) -> Result<Vec<GitCommit>, GitError> {
+    collect_matching_commits(repo, None, limit, |commit| {
+        let seconds = commit.time().seconds();
+        seconds >= from_ts && seconds <= to_ts
+    })
+}
+
+fn collect_matching_commits<F>(
+    repo: &Repository,
+    branch_name: Option<&str>,
+    limit: usize,
+    mut matches: F,
+) -> Result<Vec<GitCommit>, GitError>
+where
+    F: FnMut(&git2::Commit) -> bool,
+{
+    let mut revwalk = repo.revwalk()?;
+    push_commit_sources(repo, &mut revwalk, false, branch_name)?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let (branch_map, tag_map) = get_ref_maps(repo)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        if matches(&commit) {
+            commits.push(to_git_commit(&commit, &branch_map, &tag_map));
+        }
+    }
+
+    graph::calculate_lanes(&mut commits);
+
+    Ok(commits)
+}
+
+pub fn get_commits_between(
+    repo: &Repository,
+    exclude: &str,
+    include: &str,
+    limit: usize,
+) -> Result<Vec<GitCommit>, GitError> {
+    let include_oid = repo.revparse_single(include)?.peel_to_commit()?.id();
+    let exclude_oid = repo.revparse_single(exclude)?.peel_to_commit()?.id();
+
+    // Equivalent to `git log exclude..include`
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(include_oid)?;
+    revwalk.hide(exclude_oid)?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let (branch_map, tag_map) = get_ref_maps(repo)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        let commit = repo.find_commit(oid?)?;
+        commits.push(to_git_commit(&commit, &branch_map, &tag_map));
+    }
+
+    graph::calculate_lanes(&mut commits);
+
+    Ok(commits)
+}
+
+pub fn compare_branches(
+    repo: &Repository,
+    base: &str,
+    compare: &str,
+    limit: usize,
+) -> Result<BranchComparison, GitError> {
+    let merge_base = get_merge_base(repo, base, compare)?;
+
+    Ok(BranchComparison {
+        ahead: get_commits_between(repo, base, compare, limit)?,
+        behind: get_commits_between(repo, compare, base, limit)?,
+        merge_base,
+    })
+}
+
+pub fn get_graph_only(
+    repo: &Repository,
+    commit_ids: Vec<String>,
+) -> Result<Vec<GraphPosition>, GitError> {
+    // Lanes only depend on parent links, so skip messages, refs and signatures
+    // Lay out by full hash so parent links match even when given short ids
+    let resolved = commit_ids
+        .iter()
+        .map(|id| {
+            let commit = repo.revparse_single(id)?.peel_to_commit()?;
+            let parents = commit
+                .parent_ids()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>();
+            Ok((commit.id().to_string(), parents))
+        })
+        .collect::<Result<Vec<_>, GitError>>()?;
+
+    let layout = graph::layout(
+        resolved
+            .iter()
+            .map(|(id, parents)| (id.as_str(), parents.as_slice())),
+    );
+
+    Ok(commit_ids
+        .into_iter()
+        .zip(layout)
+        .map(|(id, info)| GraphPosition {
+            id,
+            lane: info.lane,
+            lines: info.lines,
         })
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git apply: {}", e)))?;
+        .collect())
+}
 
-    if !output.status.success() {
+type RefMap = std::collections::HashMap<git2::Oid, Vec<String>>;
+
+// Branch and tag names keyed by the commit they point at
+fn get_ref_maps(repo: &Repository) -> Result<(RefMap, RefMap), GitError> {
+    let mut branch_map = RefMap::new();
+    let mut tag_map = RefMap::new();
+
+    for branch in repo.branches(None)? {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            if let Some(target) = branch.get().target() {
+                branch_map.entry(target).or_default().push(name.to_string());
+            }
+        }
+    }
+
+    repo.tag_foreach(|oid, name| {
+        if let Ok(name_str) = std::str::from_utf8(name) {
+            if let Some(tag_name) = name_str.strip_prefix("refs/tags/") {
+                tag_map.entry(oid).or_default().push(tag_name.to_string());
+            }
+        }
+        true
+    })?;
+
+    Ok((branch_map, tag_map))
+}
+
+fn to_git_commit(commit: &git2::Commit, branch_map: &RefMap, tag_map: &RefMap) -> GitCommit {
+    let oid = commit.id();
+    let parents: Vec<String> = commit.parents().map(|p| p.id().to_string()).collect();
+
+    GitCommit {
+        id: oid.to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author: commit.author().name().unwrap_or("").to_string(),
+        email: commit.author().email().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds().to_string(),
+        parents,
+        branches: branch_map.get(&oid).cloned(),
+        tags: tag_map.get(&oid).cloned(),
+        lane: 0,
+        lines: Vec::new(),
+    }
+}
+
+// Whether the commit changed anything matching the diff options' pathspec
+fn commit_touches_path(
+    repo: &Repository,
+    commit: &git2::Commit,
+    diff_options: &mut git2::DiffOptions,
+) -> Result<bool, GitError> {
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(diff_options))?;
+    Ok(diff.deltas().len() > 0)
+}
+
+pub fn get_file_history(
+    repo: &Repository,
+    file_path: &str,
+    limit: usize,
+) -> Result<Vec<GitCommit>, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.pathspec(file_path);
+
+    let (branch_map, tag_map) = get_ref_maps(repo)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        if commit_touches_path(repo, &commit, &mut diff_options)? {
+            commits.push(to_git_commit(&commit, &branch_map, &tag_map));
+        }
+    }
+
+    Ok(commits)
+}
+
+pub fn get_commits_touching_file(
+    repo: &Repository,
+    file_path: &str,
+    limit: usize,
+    follow_renames: bool,
+) -> Result<Vec<GitCommit>, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    // Children must come before parents for the path tracking below
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    let (branch_map, tag_map) = get_ref_maps(repo)?;
+
+    // Renames make the file live under an older name further back in history
+    let mut current_path = file_path.to_string();
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0)?.tree()?)
+        } else {
+            None
+        };
+
+        let mut diff_options = git2::DiffOptions::new();
+        diff_options.pathspec(&current_path);
+        let diff =
+            repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_options))?;
+        let Some(delta) = diff.deltas().next() else {
+            continue;
+        };
+        let added = delta.status() == git2::Delta::Added;
+
+        commits.push(to_git_commit(&commit, &branch_map, &tag_map));
+
+        // The pathspec hides the other side of a rename, so when the file first
+        // appears here, diff the whole tree to find where it came from
+        if follow_renames && added && parent_tree.is_some() {
+            let mut full_diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+            let mut find_options = git2::DiffFindOptions::new();
+            find_options.renames(true);
+            full_diff.find_similar(Some(&mut find_options))?;
+
+            let renamed_from = full_diff
+                .deltas()
+                .filter(|d| d.status() == git2::Delta::Renamed)
+                .find(|d| d.new_file().path() == Some(Path::new(&current_path)))
+                .and_then(|d| d.old_file().path().and_then(|p| p.to_str()))
+                .map(|p| p.to_string());
+            if let Some(old_path) = renamed_from {
+                current_path = old_path;
+            }
+        }
+    }
+
+    // Commits that don't touch the file are skipped, so lay the matches out as a
+    // single chain rather than leaving lanes open for parents that never show up
+    let chain: Vec<Vec<String>> = (0..commits.len())
+        .map(|i| {
+            commits
+                .get(i + 1)
+                .map(|c| c.id.clone())
+                .into_iter()
+                .collect()
+        })
+        .collect();
+    let layout = graph::layout(
+        commits
+            .iter()
+            .zip(&chain)
+            .map(|(commit, parents)| (commit.id.as_str(), parents.as_slice())),
+    );
+    for (commit, info) in commits.iter_mut().zip(layout) {
+        commit.lane = info.lane;
+        commit.lines = info.lines;
+    }
+
+    Ok(commits)
+}
+
+pub fn search_commits(
+    repo: &Repository,
+    query: &str,
+    search_in: SearchIn,
+    limit: usize,
+) -> Result<Vec<GitCommit>, GitError> {
+    let mut revwalk = repo.revwalk()?;
+    for branch in repo.branches(None)? {
+        let (branch, _) = branch?;
+        if let Some(target) = branch.get().target() {
+            revwalk.push(target)?;
+        }
+    }
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let needle = query.to_lowercase();
+    let (branch_map, tag_map) = get_ref_maps(repo)?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        if commits.len() >= limit {
+            break;
+        }
+
+        let commit = repo.find_commit(oid?)?;
+        let matched = match search_in {
+            SearchIn::Message => contains_ignore_case(commit.message(), &needle),
+            SearchIn::Author => contains_ignore_case(commit.author().name(), &needle),
+            SearchIn::Email => contains_ignore_case(commit.author().email(), &needle),
+            SearchIn::Diff => diff_contains(repo, &commit, &needle)?,
+        };
+
+        if matched {
+            commits.push(to_git_commit(&commit, &branch_map, &tag_map));
+        }
+    }
+
+    Ok(commits)
+}
+
+fn contains_ignore_case(haystack: Option<&str>, needle: &str) -> bool {
+    haystack.is_some_and(|h| h.to_lowercase().contains(needle))
+}
+
+// Whether any added or removed line in the commit contains the needle
+fn diff_contains(repo: &Repository, commit: &git2::Commit, needle: &str) -> Result<bool, GitError> {
+    let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+    let mut found = false;
+    let result = diff.foreach(
+        &mut |_, _| true,
+        None,
+        None,
+        Some(&mut |_, _, line| {
+            if matches!(line.origin(), '+' | '-')
+                && String::from_utf8_lossy(line.content())
+                    .to_lowercase()
+                    .contains(needle)
+            {
+                found = true;
+                return false;
+            }
+            true
+        }),
+    );
+
+    // Stopping early on a match is reported as a user abort
+    if !found {
+        result?;
+    }
+
+    Ok(found)
+}
+
+const MAX_GREP_MATCHES: usize = 1000;
+
+pub fn git_grep(
+    repo: &Repository,
+    pattern: &str,
+    commit_id: Option<&str>,
+    case_insensitive: bool,
+    word_match: bool,
+) -> Result<GrepResult, GitError> {
+    // -z separates path, line number and content with NULs so colons in paths are safe
+    let mut args = vec!["grep", "--line-number", "-z"];
+    if case_insensitive {
+        args.push("-i");
+    }
+    if word_match {
+        args.push("-w");
+    }
+    args.push("-e");
+    args.push(pattern);
+
+    // An empty commit searches the working tree
+    let commit_id = commit_id.filter(|c| !c.is_empty());
+    if let Some(commit) = commit_id {
+        args.push(commit);
+    }
+    args.push("--");
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .current_dir(repo.workdir().unwrap_or(repo.path()))
+        .output()
+        .map_err(|e| GitError::Unknown(format!("Failed to run git grep: {}", e)))?;
+
+    // Exit code 1 just means nothing matched
+    if !output.status.success() && output.status.code() != Some(1) {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(&format!(
-            "git apply failed: {}",
-            stderr
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut matches = Vec::new();
+    let mut truncated = false;
+
+    for line in stdout.lines() {
+        if matches.len() >= MAX_GREP_MATCHES {
+            truncated = true;
+            break;
+        }
+
+        let mut parts = line.splitn(3, '\0');
+        let (Some(path), Some(line_no), Some(content)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let Ok(line_no) = line_no.parse() else {
+            continue;
+        };
+
+        // Matches in a commit are reported as "<commit>:<path>"
+        let path = commit_id
+            .and_then(|commit| path.strip_prefix(commit))
+            .and_then(|rest| rest.strip_prefix(':'))
+            .unwrap_or(path);
+
+        matches.push(GrepMatch {
+            path: path.to_string(),
+            line_no,
+            content: content.to_string(),
+        });
+    }
+
+    Ok(GrepResult { matches, truncated })
+}
+
+pub fn get_file_at_commit(
+    repo: &Repository,
+    commit_id: &str,
+    file_path: &str,
+) -> Result<String, GitError> {
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+    let blob = find_blob_in_commit(repo, &commit, file_path)?;
+
+    if is_binary(blob.content()) {
+        return Err(GitError::Unknown(format!(
+            "'{}' is a binary file",
+            file_path
+        )));
+    }
+
+    String::from_utf8(blob.content().to_vec())
+        .map_err(|_| GitError::Unknown(format!("'{}' is not valid UTF-8", file_path)))
+}
+
+pub fn checkout_file(repo: &Repository, file_path: &str, commit_id: &str) -> Result<(), GitError> {
+    let workdir = repo.workdir().ok_or_else(|| {
+        GitError::Unknown("Cannot check out files in a bare repository".to_string())
+    })?;
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
+    let blob = find_blob_in_commit(repo, &commit, file_path)?;
+
+    // Only the working tree is touched; the restored version shows up as an unstaged change
+    let target = workdir.join(file_path);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| GitError::Unknown(format!("Failed to create directory: {}", e)))?;
+    }
+    std::fs::write(&target, blob.content())
+        .map_err(|e| GitError::Unknown(format!("Failed to write '{}': {}", file_path, e)))
+}
+
+pub fn get_tree_entries(
+    repo: &Repository,
+    commit_id: &str,
+    tree_path: &str,
+) -> Result<Vec<TreeEntry>, GitError> {
+    let root = repo.revparse_single(commit_id)?.peel_to_tree()?;
+    let tree_path = tree_path.trim_matches('/');
+
+    let tree = if tree_path.is_empty() {
+        root
+    } else {
+        root.get_path(Path::new(tree_path))?
+            .to_object(repo)?
+            .peel_to_tree()?
+    };
+
+    let odb = repo.odb()?;
+    let mut entries = Vec::new();
+
+    for entry in tree.iter() {
+        let name = entry.name().unwrap_or("").to_string();
+        let path = if tree_path.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", tree_path, name)
+        };
+
+        let (kind, size) = match entry.kind() {
+            Some(git2::ObjectType::Tree) => ("tree", None),
+            // Submodules are recorded as commits from another repository
+            Some(git2::ObjectType::Commit) => ("commit", None),
+            _ => {
+                // Read just the object header to avoid loading the blob
+                let size = odb
+                    .read_header(entry.id())
+                    .ok()
+                    .map(|(size, _)| size as u64);
+                ("blob", size)
+            }
+        };
+
+        entries.push(TreeEntry {
+            name,
+            path,
+            kind: kind.to_string(),
+            oid: entry.id().to_string(),
+            size,
+        });
+    }
+
+    Ok(entries)
+}
+
+fn find_blob_in_commit<'r>(
+    repo: &'r Repository,
+    commit: &git2::Commit,
+    path: &str,
+) -> Result<git2::Blob<'r>, GitError> {
+    Ok(commit
+        .tree()?
+        .get_path(Path::new(path))?
+        .to_object(repo)?
+        .peel_to_blob()?)
+}
+
+// Same heuristic as git: a NUL byte in the first 8 KB means binary
+fn is_binary(content: &[u8]) -> bool {
+    content.iter().take(8000).any(|&b| b == 0)
+}
+
+pub fn get_blame(
+    repo: &Repository,
+    path: &str,
+    commit_id: Option<&str>,
+) -> Result<Vec<GitBlameLine>, GitError> {
+    blame_file_lines(repo, path, commit_id, &mut git2::BlameOptions::new())
+}
+
+pub fn get_blame_range(
+    repo: &Repository,
+    path: &str,
+    start_line: u32,
+    end_line: u32,
+    commit_id: Option<&str>,
+) -> Result<Vec<GitBlameLine>, GitError> {
+    if start_line == 0 || end_line < start_line {
+        return Err(GitError::Unknown(format!(
+            "Invalid line range {}-{}",
+            start_line, end_line
         )));
     }
 
-    Ok(())
+    let mut options = git2::BlameOptions::new();
+    options
+        .min_line(start_line as usize)
+        .max_line(end_line as usize);
+    blame_file_lines(repo, path, commit_id, &mut options)
 }
 
-pub fn discard_file(repo: &Repository, file_path: &str) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
-
-    // Use git checkout -- <file> to discard changes
-    let output = std::process::Command::new("git")
-        .args(["checkout", "--", file_path])
-        .current_dir(workdir)
-        .output()
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git checkout: {}", e)))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(&format!(
-            "git checkout failed: {}",
-            stderr
+fn blame_file_lines(
+    repo: &Repository,
+    path: &str,
+    commit_id: Option<&str>,
+    options: &mut git2::BlameOptions,
+) -> Result<Vec<GitBlameLine>, GitError> {
+    let commit = repo
+        .revparse_single(commit_id.unwrap_or("HEAD"))?
+        .peel_to_commit()?;
+    options.newest_commit(commit.id());
+
+    // Line contents come from the blob at the blamed revision
+    let blob = find_blob_in_commit(repo, &commit, path)?;
+    if is_binary(blob.content()) {
+        return Err(GitError::Unknown(format!(
+            "Cannot blame binary file '{}'",
+            path
         )));
     }
+    let content = String::from_utf8_lossy(blob.content());
+    let lines: Vec<&str> = content.lines().collect();
+
+    let blame = repo.blame_file(Path::new(path), Some(options))?;
+
+    let mut blame_lines = Vec::new();
+    for hunk in blame.iter() {
+        let signature = hunk.final_signature();
+        let commit_id = hunk.final_commit_id().to_string();
+        let author = signature.name().unwrap_or("").to_string();
+        let email = signature.email().unwrap_or("").to_string();
+        let timestamp = signature.when().seconds().to_string();
+
+        // A max_line past the end of the file yields hunks with no content
+        let start = hunk.final_start_line();
+        let end = (start + hunk.lines_in_hunk()).min(lines.len() + 1);
+        for line_no in start..end {
+            blame_lines.push(GitBlameLine {
+                line_no: line_no as u32,
+                commit_id: commit_id.clone(),
+                author: author.clone(),
+                email: email.clone(),
+                timestamp: timestamp.clone(),
+                content: lines.get(line_no - 1).unwrap_or(&"").to_string(),
+            });
+        }
+    }
 
-    Ok(())
+    Ok(blame_lines)
 }
 
-pub fn discard_hunk(
+pub fn get_commit_diff(
     repo: &Repository,
-    _file_path: &str,
-    full_diff: &str,
-    hunk_header: &str,
-    hunk_lines: &str,
-) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
-
-    let diff_lines: Vec<&str> = full_diff.lines().collect();
-    let diff_header = diff_lines
-        .iter()
-        .take(4)
-        .cloned()
-        .collect::<Vec<&str>>()
-        .join("\n");
+    commit_id: &str,
+    rename_threshold: Option<u32>,
+) -> Result<Vec<CommitFile>, GitError> {
+    diff_commit_against_parent(repo, commit_id, 0, rename_threshold)
+}
 
-    let patch = format!("{}\n{}\n{}\n", diff_header, hunk_header, hunk_lines);
+pub fn get_commit_diff_with_parent(
+    repo: &Repository,
+    commit_id: &str,
+    parent_index: usize,
+) -> Result<Vec<CommitFile>, GitError> {
+    diff_commit_against_parent(repo, commit_id, parent_index, None)
+}
 
-    // Use git apply --reverse (without --cached) to discard working tree changes
-    let output = std::process::Command::new("git")
-        .args([
-            "apply",
-            "--unidiff-zero",
-            "--reverse",
-            "--ignore-whitespace",
-        ])
-        .current_dir(workdir)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .and_then(|mut child| {
-            use std::io::Write;
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(patch.as_bytes())?;
-            }
-            child.wait_with_output()
-        })
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git apply: {}", e)))?;
+fn diff_commit_against_parent(
+    repo: &Repository,
+    commit_id: &str,
+    parent_index: usize,
+    rename_threshold: Option<u32>,
+) -> Result<Vec<CommitFile>, GitError> {
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(&format!(
-            "git apply failed: {}",
-            stderr
+    let commit_tree = commit.tree()?;
+    // Root commits are diffed against an empty tree
+    let parent_tree = if commit.parent_count() == 0 {
+        None
+    } else if parent_index < commit.parent_count() {
+        Some(commit.parent(parent_index)?.tree()?)
+    } else {
+        return Err(GitError::Unknown(format!(
+            "Commit {} has no parent at index {}",
+            commit_id, parent_index
         )));
+    };
+
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
+    if let Some(threshold) = rename_threshold {
+        find_renames(&mut diff, threshold)?;
     }
+    collect_commit_files(&diff)
+}
 
-    Ok(())
+fn find_renames(diff: &mut git2::Diff, threshold: u32) -> Result<(), GitError> {
+    let mut find_options = git2::DiffFindOptions::new();
+    find_options
+        .renames(true)
+        .rename_threshold(rename_threshold_percent(threshold));
+    Ok(diff.find_similar(Some(&mut find_options))?)
 }
 
-pub fn checkout_branch(repo: &Repository, branch_name: &str) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
+// Similarity is a percentage, so anything above 100 means an exact match
+fn rename_threshold_percent(threshold: u32) -> u16 {
+    threshold.min(100) as u16
+}
 
-    // Use git checkout with error handling
-    let output = std::process::Command::new("git")
-        .args(["checkout", branch_name])
-        .current_dir(workdir)
-        .output()
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git checkout: {}", e)))?;
+pub fn get_patch_id(repo: &Repository, commit_id: &str) -> Result<String, GitError> {
+    let commit = repo.revparse_single(commit_id)?.peel_to_commit()?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(stderr.as_ref()));
-    }
+    let commit_tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
 
-    Ok(())
+    // Same stable hash as `git patch-id`, so cherry-picks match across branches
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
+    Ok(diff.patchid(None)?.to_string())
 }
 
-pub fn fetch_remote(repo: &Repository, remote_name: &str) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
+pub fn format_patch(
+    repo: &Repository,
+    range: &str,
+    output_dir: &str,
+) -> Result<Vec<String>, GitError> {
+    // Only a commit count like "-3" may start with a dash; anything else would be read as an option
+    if let Some(count) = range.strip_prefix('-') {
+        if count.is_empty() || !count.chars().all(|c| c.is_ascii_digit()) {
+            return Err(GitError::Unknown(format!("Invalid range: {}", range)));
+        }
+    }
 
     let output = std::process::Command::new("git")
-        .args(["fetch", remote_name])
-        .current_dir(workdir)
+        .args(["format-patch", range, "-o", output_dir])
+        .current_dir(repo.workdir().unwrap_or(repo.path()))
         .output()
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git fetch: {}", e)))?;
+        .map_err(|e| GitError::Unknown(format!("Failed to run git format-patch: {}", e)))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(stderr.as_ref()));
+        return Err(GitError::Unknown(stderr.as_ref().to_string()));
     }
 
-    Ok(())
+    // git prints one generated patch file per line
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
 }
 
-pub fn pull_remote(repo: &Repository, remote_name: &str) -> Result<(), git2::Error> {
-    let workdir = repo.workdir().unwrap();
+pub fn apply_patch(
+    repo: &Repository,
+    patch_path: &str,
+    cached: bool,
+    three_way: bool,
+) -> Result<ApplyResult, GitError> {
+    let mut args = vec!["apply"];
+    if cached {
+        args.push("--cached");
+    }
+    if three_way {
+        args.push("--3way");
+    }
+    args.push(patch_path);
+
+    let workdir = repo.workdir().ok_or_else(|| {
+        GitError::Unknown("Cannot apply patches in a bare repository".to_string())
+    })?;
 
     let output = std::process::Command::new("git")
-        .args(["pull", remote_name])
+        .args(&args)
         .current_dir(workdir)
         .output()
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git pull: {}", e)))?;
+        .map_err(|e| GitError::Unknown(format!("Failed to run git apply: {}", e)))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(git2::Error::from_str(stderr.as_ref()));
+    if output.status.success() {
+        return Ok(ApplyResult {
+            applied: true,
+            conflicts: Vec::new(),
+        });
     }
 
-    Ok(())
-}
-
-pub fn get_commits(
-    repo: &Repository,
-    limit: usize,
-    local_only: bool,
-    branch_name: Option<&str>,
-) -> Result<Vec<GitCommit>, git2::Error> {
-    let mut revwalk = repo.revwalk()?;
-
-    // If specific branch requested, only walk from that branch
-    if let Some(branch) = branch_name {
-        // Try local branch first
-        if let Ok(b) = repo.find_branch(branch, git2::BranchType::Local) {
-            if let Some(target) = b.get().target() {
-                revwalk.push(target)?;
-            }
-        } else if let Ok(b) = repo.find_branch(branch, git2::BranchType::Remote) {
-            // Try remote branch
-            if let Some(target) = b.get().target() {
-                revwalk.push(target)?;
-            }
-        } else {
-            // Try as reference
-            let ref_name = format!("refs/remotes/{}", branch);
-            if let Ok(reference) = repo.find_reference(&ref_name) {
-                if let Some(target) = reference.target() {
-                    revwalk.push(target)?;
-                }
+    // Three-way merges report "U <path>"; plain applies report "error: <path>: patch does not apply"
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut conflicts = Vec::new();
+    for line in stderr.lines() {
+        let path = line.strip_prefix("U ").or_else(|| {
+            line.strip_prefix("error: ")
+                .and_then(|rest| rest.strip_suffix(": patch does not apply"))
+        });
+        if let Some(path) = path {
+            if !conflicts.iter().any(|c| c == path) {
+                conflicts.push(path.to_string());
             }
         }
-    } else {
-        // Push branches based on filter
-        let branch_type = if local_only {
-            Some(git2::BranchType::Local)
-        } else {
-            None // All branches
-        };
+    }
 
-        for branch in repo.branches(branch_type)? {
-            let (branch, _) = branch?;
-            if let Some(target) = branch.get().target() {
-                revwalk.push(target)?;
-            }
-        }
+    if conflicts.is_empty() {
+        return Err(GitError::Unknown(stderr.trim().to_string()));
     }
 
-    revwalk.set_sorting(git2::Sort::TIME)?;
+    Ok(ApplyResult {
+        applied: false,
+        conflicts,
+    })
+}
 
-    let mut branch_map: std::collections::HashMap<git2::Oid, Vec<String>> =
-        std::collections::HashMap::new();
-    let mut tag_map: std::collections::HashMap<git2::Oid, Vec<String>> =
-        std::collections::HashMap::new();
+pub fn get_log_stats(
+    repo: &Repository,
+    commit_ids: Vec<String>,
+) -> Result<Vec<GitCommitStat>, GitError> {
+    let mut stats = Vec::with_capacity(commit_ids.len());
 
-    for branch in repo.branches(None)? {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            if let Some(target) = branch.get().target() {
-                branch_map.entry(target).or_default().push(name.to_string());
-            }
-        }
-    }
+    for commit_id in commit_ids {
+        let commit = repo.revparse_single(&commit_id)?.peel_to_commit()?;
 
-    repo.tag_foreach(|oid, name| {
-        if let Ok(name_str) = std::str::from_utf8(name) {
-            if let Some(tag_name) = name_str.strip_prefix("refs/tags/") {
-                tag_map.entry(oid).or_default().push(tag_name.to_string());
-            }
-        }
-        true
-    })?;
+        let commit_tree = commit.tree()?;
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0)?.tree()?)
+        } else {
+            None
+        };
 
-    let mut commits = Vec::new();
-    for (i, oid) in revwalk.enumerate() {
-        if i >= limit {
-            break;
-        }
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
+        let diff_stats = diff.stats()?;
 
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-        let parents: Vec<String> = commit.parents().map(|p| p.id().to_string()).collect();
-        let branches = branch_map.get(&oid).cloned();
-        let tags = tag_map.get(&oid).cloned();
-
-        commits.push(GitCommit {
-            id: commit.id().to_string(),
-            message: commit.message().unwrap_or("").to_string(),
-            author: commit.author().name().unwrap_or("").to_string(),
-            email: commit.author().email().unwrap_or("").to_string(),
-            timestamp: commit.time().seconds().to_string(),
-            parents,
-            branches,
-            tags,
-            lane: 0,
-            lines: Vec::new(),
+        stats.push(GitCommitStat {
+            id: commit_id,
+            additions: diff_stats.insertions(),
+            deletions: diff_stats.deletions(),
+            files_changed: diff_stats.files_changed(),
         });
     }
 
-    // Calculate lanes and lines
-    calculate_lanes(&mut commits);
-
-    Ok(commits)
+    Ok(stats)
 }
 
-fn calculate_lanes(commits: &mut [GitCommit]) {
-    struct Lane {
-        sha: Option<String>,
-        color_index: usize,
-    }
-
-    let mut lanes: Vec<Option<Lane>> = Vec::new();
-    let mut color_counter = 0;
-
-    for commit in commits.iter_mut() {
-        let commit_id = commit.id.clone();
-        let mut new_lanes: Vec<Option<Lane>> = Vec::new();
-        let mut current_lane: Option<usize> = None;
-        let mut current_color = 0;
-        let mut found_first = false;
-        let mut lines: Vec<GraphLine> = Vec::new();
-
-        // First, iterate over existing lanes and pass through any that don't want this commit
-        for (i, lane) in lanes.iter().enumerate() {
-            if let Some(lane_data) = lane {
-                // This lane is expecting our commit
-                if lane_data.sha.as_ref() == Some(&commit_id) {
-                    if !found_first {
-                        found_first = true;
-                        current_lane = Some(new_lanes.len());
-                        current_color = lane_data.color_index;
-                        new_lanes.push(Some(Lane {
-                            sha: None, // Will be set to first parent
-                            color_index: lane_data.color_index,
-                        }));
-                        // Upper line from previous lane to current position
-                        lines.push(GraphLine {
-                            upper: true,
-                            from: i,
-                            to: new_lanes.len() - 1,
-                            color: lane_data.color_index,
-                        });
-                        // Lower line at current position
-                        if !commit.parents.is_empty() {
-                            lines.push(GraphLine {
-                                upper: false,
-                                from: new_lanes.len() - 1,
-                                to: new_lanes.len() - 1,
-                                color: lane_data.color_index,
-                            });
-                        }
-                    } else {
-                        // Merge - this lane converges to current_lane
-                        if let Some(cur_lane) = current_lane {
-                            lines.push(GraphLine {
-                                upper: true,
-                                from: i,
-                                to: cur_lane,
-                                color: lane_data.color_index,
-                            });
-                        }
-                    }
-                } else {
-                    // Not our commit, pass through
-                    new_lanes.push(Some(Lane {
-                        sha: lane_data.sha.clone(),
-                        color_index: lane_data.color_index,
-                    }));
-                    // Pass-through lines
-                    lines.push(GraphLine {
-                        upper: true,
-                        from: i,
-                        to: new_lanes.len() - 1,
-                        color: lane_data.color_index,
-                    });
-                    lines.push(GraphLine {
-                        upper: false,
-                        from: new_lanes.len() - 1,
-                        to: new_lanes.len() - 1,
-                        color: lane_data.color_index,
-                    });
-                }
-            } else {
-                // Empty lane
-                new_lanes.push(None);
-            }
-        }
-
-        // If we didn't find a lane expecting us, create new one
-        if !found_first && !commit.parents.is_empty() {
-            current_lane = Some(new_lanes.len());
-            current_color = color_counter;
-            new_lanes.push(Some(Lane {
-                sha: None,
-                color_index: color_counter,
-            }));
-            // Lower line for new commit
-            lines.push(GraphLine {
-                upper: false,
-                from: new_lanes.len() - 1,
-                to: new_lanes.len() - 1,
-                color: color_counter,
-            });
-            color_counter += 1;
-        }
-
-        // Set the lane for this commit
-        commit.lane = current_lane.unwrap_or(0);
-        commit.lines = lines;
-
-        // Update current lane to point to first parent
-        if let Some(lane_idx) = current_lane {
-            if let Some(first_parent) = commit.parents.first() {
-                if let Some(Some(lane)) = new_lanes.get_mut(lane_idx) {
-                    lane.sha = Some(first_parent.clone());
-                }
-            } else {
-                // No parents - clear the lane
-                new_lanes[lane_idx] = None;
-            }
-        }
-
-        // Add other parents to new lanes
-        for parent_id in commit.parents.iter().skip(1) {
-            // Check if parent already in a lane
-            let mut found_lane_idx = None;
-            for (idx, lane) in new_lanes.iter().enumerate() {
-                if let Some(lane_data) = lane {
-                    if lane_data.sha.as_ref() == Some(parent_id) {
-                        found_lane_idx = Some(idx);
-                        break;
-                    }
-                }
-            }
-
-            if let Some(parent_lane_idx) = found_lane_idx {
-                // Parent already has a lane, draw line to it
-                if let Some(cur_lane) = current_lane {
-                    // Use the parent lane's color
-                    let parent_color = new_lanes[parent_lane_idx]
-                        .as_ref()
-                        .map(|l| l.color_index)
-                        .unwrap_or(current_color);
-                    commit.lines.push(GraphLine {
-                        upper: false,
-                        from: cur_lane,
-                        to: parent_lane_idx,
-                        color: parent_color,
-                    });
-                }
-            } else {
-                // Find empty lane or create new one
-                let empty_idx = new_lanes.iter().position(|l| l.is_none());
-                let new_lane_idx = if let Some(idx) = empty_idx {
-                    new_lanes[idx] = Some(Lane {
-                        sha: Some(parent_id.clone()),
-                        color_index: color_counter,
-                    });
-                    idx
-                } else {
-                    new_lanes.push(Some(Lane {
-                        sha: Some(parent_id.clone()),
-                        color_index: color_counter,
-                    }));
-                    new_lanes.len() - 1
-                };
-
-                // Draw line from current commit to new parent lane
-                if let Some(cur_lane) = current_lane {
-                    commit.lines.push(GraphLine {
-                        upper: false,
-                        from: cur_lane,
-                        to: new_lane_idx,
-                        color: color_counter,
-                    });
-                }
-                color_counter += 1;
-            }
-        }
+pub fn get_diff_between_commits(
+    repo: &Repository,
+    from_id: &str,
+    to_id: &str,
+) -> Result<Vec<CommitFile>, GitError> {
+    let from_tree = repo.revparse_single(from_id)?.peel_to_tree()?;
+    let to_tree = repo.revparse_single(to_id)?.peel_to_tree()?;
+
+    let diff = repo.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), None)?;
+    collect_commit_files(&diff)
+}
 
-        lanes = new_lanes;
+fn delta_status(status: git2::Delta) -> &'static str {
+    match status {
+        git2::Delta::Added => "added",
+        git2::Delta::Deleted => "deleted",
+        git2::Delta::Modified => "modified",
+        git2::Delta::Renamed => "renamed",
+        git2::Delta::Copied => "copied",
+        git2::Delta::Untracked => "untracked",
+        _ => "unknown",
     }
 }
 
-pub fn get_commit_diff(repo: &Repository, commit_id: &str) -> Result<Vec<CommitFile>, git2::Error> {
-    let oid = git2::Oid::from_str(commit_id)
-        .map_err(|e| git2::Error::from_str(&format!("Invalid commit ID: {}", e)))?;
-    let commit = repo.find_commit(oid)?;
-
-    let commit_tree = commit.tree()?;
-    let parent_tree = if commit.parent_count() > 0 {
-        Some(commit.parent(0)?.tree()?)
-    } else {
-        None
-    };
-
-    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit_tree), None)?;
-
+fn collect_commit_files(diff: &git2::Diff) -> Result<Vec<CommitFile>, GitError> {
     let mut files = Vec::new();
 
     for (delta_idx, delta) in diff.deltas().enumerate() {
@@ -963,22 +5228,17 @@ pub fn get_commit_diff(repo: &Repository, commit_id: &str) -> Result<Vec<CommitF
             None
         };
 
-        let status = match delta.status() {
-            git2::Delta::Added => "added",
-            git2::Delta::Deleted => "deleted",
-            git2::Delta::Modified => "modified",
-            git2::Delta::Renamed => "renamed",
-            git2::Delta::Copied => "copied",
-            _ => "unknown",
-        }
-        .to_string();
+        let status = delta_status(delta.status()).to_string();
 
         let mut lines = Vec::new();
         let mut additions = 0;
         let mut deletions = 0;
+        let mut is_binary = false;
 
-        let patch = git2::Patch::from_diff(&diff, delta_idx)?;
+        let patch = git2::Patch::from_diff(diff, delta_idx)?;
         if let Some(patch) = patch {
+            // Binary detection happens while the patch is generated
+            is_binary = patch.delta().flags().is_binary();
             for hunk_idx in 0..patch.num_hunks() {
                 let (hunk, _) = patch.hunk(hunk_idx)?;
 
@@ -1029,8 +5289,135 @@ pub fn get_commit_diff(repo: &Repository, commit_id: &str) -> Result<Vec<CommitF
             additions,
             deletions,
             lines,
+            is_binary,
+            binary_size_old: is_binary.then(|| old_file.size()),
+            binary_size_new: is_binary.then(|| new_file.size()),
         });
     }
 
     Ok(files)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(code: git2::ErrorCode, class: git2::ErrorClass, message: &str) -> GitError {
+        git2::Error::new(code, class, message).into()
+    }
+
+    fn serialized_code(error: &GitError) -> String {
+        serde_json::to_value(error).unwrap()["code"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn missing_repository_is_not_a_repository() {
+        let error = map(
+            git2::ErrorCode::NotFound,
+            git2::ErrorClass::Repository,
+            "could not find repository at '/tmp'",
+        );
+        assert!(matches!(error, GitError::NotARepository));
+        assert_eq!(serialized_code(&error), "not_a_repository");
+    }
+
+    #[test]
+    fn missing_branch_extracts_the_name() {
+        let error = map(
+            git2::ErrorCode::NotFound,
+            git2::ErrorClass::Reference,
+            "cannot locate local branch 'feature/x'",
+        );
+        assert!(matches!(&error, GitError::BranchNotFound(name) if name == "feature/x"));
+        assert_eq!(serialized_code(&error), "branch_not_found");
+
+        let error = map(
+            git2::ErrorCode::NotFound,
+            git2::ErrorClass::Reference,
+            "reference 'refs/heads/main' not found",
+        );
+        assert!(matches!(&error, GitError::BranchNotFound(name) if name == "main"));
+    }
+
+    #[test]
+    fn missing_non_branch_reference_is_unknown() {
+        let error = map(
+            git2::ErrorCode::NotFound,
+            git2::ErrorClass::Reference,
+            "revspec 'abc123' not found",
+        );
+        assert!(
+            matches!(&error, GitError::Unknown(message) if message == "revspec 'abc123' not found")
+        );
+        assert_eq!(serialized_code(&error), "unknown");
+    }
+
+    #[test]
+    fn conflicts_map_to_merge_conflict() {
+        let error = map(
+            git2::ErrorCode::Conflict,
+            git2::ErrorClass::Checkout,
+            "1 conflict prevents checkout",
+        );
+        assert!(matches!(&error, GitError::MergeConflict(paths) if paths.is_empty()));
+        assert_eq!(serialized_code(&error), "merge_conflict");
+    }
+
+    #[test]
+    fn auth_maps_to_auth_failed() {
+        let error = map(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Callback,
+            "credentials were rejected",
+        );
+        assert!(matches!(error, GitError::AuthFailed));
+        assert_eq!(serialized_code(&error), "auth_failed");
+    }
+
+    #[test]
+    fn transport_classes_map_to_network_error() {
+        for class in [
+            git2::ErrorClass::Net,
+            git2::ErrorClass::Http,
+            git2::ErrorClass::Ssh,
+        ] {
+            let error = map(git2::ErrorCode::GenericError, class, "connection refused");
+            assert!(
+                matches!(&error, GitError::NetworkError(message) if message == "connection refused")
+            );
+            assert_eq!(serialized_code(&error), "network_error");
+        }
+    }
+
+    #[test]
+    fn os_permission_errors_map_to_permission_denied() {
+        let error = map(
+            git2::ErrorCode::GenericError,
+            git2::ErrorClass::Os,
+            "failed to make directory '/x': Permission denied",
+        );
+        assert!(matches!(error, GitError::PermissionDenied(_)));
+        assert_eq!(serialized_code(&error), "permission_denied");
+
+        let error = map(
+            git2::ErrorCode::GenericError,
+            git2::ErrorClass::Os,
+            "failed to stat '/x': No such file or directory",
+        );
+        assert!(matches!(error, GitError::Unknown(_)));
+    }
+
+    #[test]
+    fn serializes_code_and_display_message() {
+        let error = GitError::PushRejected(vec!["refs/heads/main (non-fast-forward)".to_string()]);
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "push_rejected");
+        assert_eq!(
+            value["message"],
+            "Push rejected: refs/heads/main (non-fast-forward)"
+        );
+    }
+}
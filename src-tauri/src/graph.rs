@@ -0,0 +1,475 @@
+use crate::git_ops::GitCommit;
+use serde::Serialize;
+
+#[derive(Serialize, Clone)]
+pub struct GraphLine {
+    pub upper: bool,  // true = upper half, false = lower half
+    pub from: usize,  // starting lane
+    pub to: usize,    // ending lane
+    pub color: usize, // color index
+}
+
+// Where a single commit sits in the graph
+#[derive(Clone)]
+pub struct GraphInfo {
+    pub lane: usize,
+    pub lines: Vec<GraphLine>,
+}
+
+// A column of the graph and the commit it is waiting to reach
+pub struct Lane {
+    pub sha: Option<String>,
+    pub color_index: usize,
+}
+
+// Number of distinct hues the graph cycles through in the UI
+const MAX_LANE_COLORS: usize = 6;
+
+// Colors come from the SHA that opens a lane rather than a running counter, so a
+// branch keeps its color however much history has been loaded
+fn lane_color(sha: &str) -> usize {
+    let prefix = git2::Oid::from_str(sha)
+        .map(|oid| {
+            let bytes = oid.as_bytes();
+            u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        })
+        .unwrap_or(0);
+    prefix as usize % MAX_LANE_COLORS
+}
+
+/// Assigns each commit its lane and the line segments drawn in its row.
+pub fn calculate_lanes(commits: &mut [GitCommit]) {
+    let graph = layout(
+        commits
+            .iter()
+            .map(|commit| (commit.id.as_str(), commit.parents.as_slice())),
+    );
+
+    for (commit, info) in commits.iter_mut().zip(graph) {
+        commit.lane = info.lane;
+        commit.lines = info.lines;
+    }
+}
+
+/// Lays out `(id, parents)` pairs given newest first, in the order they are
+/// displayed.
+pub fn layout<'a, I>(commits: I) -> Vec<GraphInfo>
+where
+    I: IntoIterator<Item = (&'a str, &'a [String])>,
+{
+    let mut graph = Vec::new();
+    let mut lanes: Vec<Option<Lane>> = Vec::new();
+
+    for (commit_id, parents) in commits {
+        let mut new_lanes: Vec<Option<Lane>> = Vec::new();
+        let mut current_lane: Option<usize> = None;
+        let mut current_color = 0;
+        let mut found_first = false;
+        let mut lines: Vec<GraphLine> = Vec::new();
+
+        // First, iterate over existing lanes and pass through any that don't want this commit
+        for (i, lane) in lanes.iter().enumerate() {
+            if let Some(lane_data) = lane {
+                // This lane is expecting our commit
+                if lane_data.sha.as_deref() == Some(commit_id) {
+                    if !found_first {
+                        found_first = true;
+                        current_lane = Some(new_lanes.len());
+                        current_color = lane_data.color_index;
+                        new_lanes.push(Some(Lane {
+                            sha: None, // Will be set to first parent
+                            color_index: lane_data.color_index,
+                        }));
+                        // Upper line from previous lane to current position
+                        lines.push(GraphLine {
+                            upper: true,
+                            from: i,
+                            to: new_lanes.len() - 1,
+                            color: lane_data.color_index,
+                        });
+                        // Lower line at current position
+                        if !parents.is_empty() {
+                            lines.push(GraphLine {
+                                upper: false,
+                                from: new_lanes.len() - 1,
+                                to: new_lanes.len() - 1,
+                                color: lane_data.color_index,
+                            });
+                        }
+                    } else {
+                        // Merge - this lane converges to current_lane
+                        if let Some(cur_lane) = current_lane {
+                            lines.push(GraphLine {
+                                upper: true,
+                                from: i,
+                                to: cur_lane,
+                                color: lane_data.color_index,
+                            });
+                        }
+                    }
+                } else {
+                    // Not our commit, pass through
+                    new_lanes.push(Some(Lane {
+                        sha: lane_data.sha.clone(),
+                        color_index: lane_data.color_index,
+                    }));
+                    // Pass-through lines
+                    lines.push(GraphLine {
+                        upper: true,
+                        from: i,
+                        to: new_lanes.len() - 1,
+                        color: lane_data.color_index,
+                    });
+                    lines.push(GraphLine {
+                        upper: false,
+                        from: new_lanes.len() - 1,
+                        to: new_lanes.len() - 1,
+                        color: lane_data.color_index,
+                    });
+                }
+            } else {
+                // Empty lane
+                new_lanes.push(None);
+            }
+        }
+
+        // If we didn't find a lane expecting us, create new one
+        if !found_first && !parents.is_empty() {
+            current_lane = Some(new_lanes.len());
+            current_color = lane_color(commit_id);
+            new_lanes.push(Some(Lane {
+                sha: None,
+                color_index: current_color,
+            }));
+            // Lower line for new commit
+            lines.push(GraphLine {
+                upper: false,
+                from: new_lanes.len() - 1,
+                to: new_lanes.len() - 1,
+                color: current_color,
+            });
+        }
+
+        // Set the lane for this commit
+        let lane = current_lane.unwrap_or(0);
+
+        // Update current lane to point to first parent
+        if let Some(lane_idx) = current_lane {
+            if let Some(first_parent) = parents.first() {
+                if let Some(Some(lane)) = new_lanes.get_mut(lane_idx) {
+                    lane.sha = Some(first_parent.clone());
+                }
+            } else {
+                // No parents - clear the lane
+                new_lanes[lane_idx] = None;
+            }
+        }
+
+        // Add other parents to new lanes. Lanes opened for an octopus merge's
+        // parents are allocated left to right so no two of them share a lane.
+        let mut next_new_lane = 0;
+        for parent_id in parents.iter().skip(1) {
+            // Check if parent already in a lane
+            let mut found_lane_idx = None;
+            for (idx, lane) in new_lanes.iter().enumerate() {
+                if let Some(lane_data) = lane {
+                    if lane_data.sha.as_ref() == Some(parent_id) {
+                        found_lane_idx = Some(idx);
+                        break;
+                    }
+                }
+            }
+
+            if let Some(parent_lane_idx) = found_lane_idx {
+                // Parent already has a lane, draw line to it
+                if let Some(cur_lane) = current_lane {
+                    // Use the parent lane's color
+                    let parent_color = new_lanes[parent_lane_idx]
+                        .as_ref()
+                        .map(|l| l.color_index)
+                        .unwrap_or(current_color);
+                    lines.push(GraphLine {
+                        upper: false,
+                        from: cur_lane,
+                        to: parent_lane_idx,
+                        color: parent_color,
+                    });
+                }
+            } else {
+                // Find empty lane or create new one
+                let empty_idx =
+                    (next_new_lane..new_lanes.len()).find(|&idx| new_lanes[idx].is_none());
+                let parent_color = lane_color(parent_id);
+                let new_lane_idx = if let Some(idx) = empty_idx {
+                    new_lanes[idx] = Some(Lane {
+                        sha: Some(parent_id.clone()),
+                        color_index: parent_color,
+                    });
+                    idx
+                } else {
+                    new_lanes.push(Some(Lane {
+                        sha: Some(parent_id.clone()),
+                        color_index: parent_color,
+                    }));
+                    new_lanes.len() - 1
+                };
+
+                next_new_lane = new_lane_idx + 1;
+
+                // Draw line from current commit to new parent lane
+                if let Some(cur_lane) = current_lane {
+                    lines.push(GraphLine {
+                        upper: false,
+                        from: cur_lane,
+                        to: new_lane_idx,
+                        color: parent_color,
+                    });
+                }
+            }
+        }
+
+        graph.push(GraphInfo { lane, lines });
+        lanes = new_lanes;
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(commits: &[(&str, &[&str])]) -> Vec<GraphInfo> {
+        let owned: Vec<(String, Vec<String>)> = commits
+            .iter()
+            .map(|(id, parents)| {
+                let parents = parents.iter().map(|p| p.to_string()).collect();
+                (id.to_string(), parents)
+            })
+            .collect();
+        layout(
+            owned
+                .iter()
+                .map(|(id, parents)| (id.as_str(), parents.as_slice())),
+        )
+    }
+
+    fn commit(id: &str, parents: &[&str]) -> GitCommit {
+        GitCommit {
+            id: id.to_string(),
+            message: String::new(),
+            author: String::new(),
+            email: String::new(),
+            timestamp: String::new(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            branches: None,
+            tags: None,
+            lane: 0,
+            lines: Vec::new(),
+        }
+    }
+
+    fn lanes(graph: &[GraphInfo]) -> Vec<usize> {
+        graph.iter().map(|info| info.lane).collect()
+    }
+
+    // (from, to) of the lines drawn in the upper or lower half of a row
+    fn segments(info: &GraphInfo, upper: bool) -> Vec<(usize, usize)> {
+        info.lines
+            .iter()
+            .filter(|line| line.upper == upper)
+            .map(|line| (line.from, line.to))
+            .collect()
+    }
+
+    #[test]
+    fn linear_history_stays_in_one_lane() {
+        let graph = run(&[("C", &["B"]), ("B", &["A"]), ("A", &[])]);
+
+        assert_eq!(lanes(&graph), vec![0, 0, 0]);
+        assert_eq!(segments(&graph[0], true), vec![]);
+        assert_eq!(segments(&graph[0], false), vec![(0, 0)]);
+        assert_eq!(segments(&graph[1], true), vec![(0, 0)]);
+        assert_eq!(segments(&graph[1], false), vec![(0, 0)]);
+        // The root ends its lane
+        assert_eq!(segments(&graph[2], true), vec![(0, 0)]);
+        assert_eq!(segments(&graph[2], false), vec![]);
+    }
+
+    #[test]
+    fn empty_history_produces_no_rows() {
+        assert!(run(&[]).is_empty());
+
+        let mut commits: Vec<GitCommit> = Vec::new();
+        calculate_lanes(&mut commits);
+        assert!(commits.is_empty());
+    }
+
+    #[test]
+    fn branch_and_merge() {
+        let graph = run(&[("M", &["B", "F"]), ("F", &["P"]), ("B", &["P"]), ("P", &[])]);
+
+        assert_eq!(lanes(&graph), vec![0, 1, 0, 0]);
+        // The merge branches out to the second parent's lane
+        assert_eq!(segments(&graph[0], false), vec![(0, 0), (0, 1)]);
+        // The feature lane converges back into the fork point
+        assert_eq!(segments(&graph[3], true), vec![(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn diamond_with_two_merges() {
+        let graph = run(&[
+            ("M2", &["M1", "C"]),
+            ("C", &["B"]),
+            ("M1", &["A", "B"]),
+            ("B", &["R"]),
+            ("A", &["R"]),
+            ("R", &[]),
+        ]);
+
+        assert_eq!(lanes(&graph), vec![0, 1, 0, 1, 0, 0]);
+        // M1's second parent B is already expected by C's lane, so no new lane opens
+        assert_eq!(segments(&graph[2], false), vec![(0, 0), (1, 1), (0, 1)]);
+        assert!(graph
+            .iter()
+            .flat_map(|info| &info.lines)
+            .all(|line| line.from < 2 && line.to < 2));
+    }
+
+    #[test]
+    fn orphan_branch_gets_its_own_lane() {
+        let graph = run(&[("A2", &["A1"]), ("O2", &["O1"]), ("A1", &[]), ("O1", &[])]);
+
+        assert_eq!(lanes(&graph), vec![0, 1, 0, 1]);
+        // The two histories never connect
+        assert!(graph
+            .iter()
+            .flat_map(|info| &info.lines)
+            .all(|line| line.from == line.to));
+    }
+
+    #[test]
+    fn detached_head_above_branch_tip() {
+        // A detached commit on top of A listed before the branch tip that also points at A
+        let graph = run(&[("D", &["A"]), ("M", &["A"]), ("A", &[])]);
+
+        assert_eq!(lanes(&graph), vec![0, 1, 0]);
+        assert_eq!(segments(&graph[2], true), vec![(0, 0), (1, 0)]);
+    }
+
+    #[test]
+    fn octopus_merge_parents_get_distinct_increasing_lanes() {
+        // R is a root, so lane 0 is free again by the time the octopus merge O
+        // is laid out; P3 is already expected by T3's lane
+        let graph = run(&[
+            ("T1", &["R"]),
+            ("T2", &["O"]),
+            ("T3", &["P3"]),
+            ("R", &[]),
+            ("O", &["P1", "P2", "P3", "P4", "P5"]),
+            ("P1", &[]),
+            ("P2", &[]),
+            ("P3", &[]),
+            ("P4", &[]),
+            ("P5", &[]),
+        ]);
+
+        let merge = &graph[4];
+        let targets: Vec<usize> = merge
+            .lines
+            .iter()
+            .filter(|line| !line.upper && line.from == merge.lane)
+            .map(|line| line.to)
+            .collect();
+        assert_eq!(targets.len(), 5);
+
+        let mut unique = targets.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(
+            unique.len(),
+            targets.len(),
+            "parents share a lane: {:?}",
+            targets
+        );
+
+        // Lanes opened for P2, P4 and P5 (P3 reuses T3's lane)
+        let opened = [targets[1], targets[3], targets[4]];
+        assert!(opened.windows(2).all(|w| w[0] < w[1]), "{:?}", opened);
+    }
+
+    #[test]
+    fn long_linear_history() {
+        let ids: Vec<String> = (0..5000).map(|i| format!("{:040x}", i + 1)).collect();
+        let parents: Vec<Vec<String>> = (0..ids.len())
+            .map(|i| ids.get(i + 1).cloned().into_iter().collect())
+            .collect();
+
+        let graph = layout(
+            ids.iter()
+                .zip(&parents)
+                .map(|(id, parents)| (id.as_str(), parents.as_slice())),
+        );
+
+        assert_eq!(graph.len(), ids.len());
+        assert!(graph.iter().all(|info| info.lane == 0));
+        assert!(graph.iter().all(|info| info.lines.len() <= 2));
+    }
+
+    #[test]
+    fn parent_outside_the_walk_keeps_its_lane_open() {
+        // F's parent was cut off by the commit limit, so its lane never closes
+        let graph = run(&[("F", &["missing"]), ("M", &["A"]), ("A", &[])]);
+
+        assert_eq!(lanes(&graph), vec![0, 1, 1]);
+        assert_eq!(segments(&graph[2], true), vec![(0, 0), (1, 1)]);
+        assert_eq!(segments(&graph[2], false), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn branch_refs_do_not_affect_layout() {
+        // "feature" is listed on M but none of its commits are part of the walk
+        let mut plain = vec![commit("M", &["A"]), commit("A", &[])];
+        let mut labelled = vec![commit("M", &["A"]), commit("A", &[])];
+        labelled[0].branches = Some(vec!["main".to_string(), "feature".to_string()]);
+
+        calculate_lanes(&mut plain);
+        calculate_lanes(&mut labelled);
+
+        for (a, b) in plain.iter().zip(&labelled) {
+            assert_eq!(a.lane, b.lane);
+            assert_eq!(a.lines.len(), b.lines.len());
+        }
+    }
+
+    #[test]
+    fn colors_are_stable_across_page_sizes() {
+        let history: &[(&str, &[&str])] = &[
+            ("aa11", &["bb22", "cc33"]),
+            ("cc33", &["dd44"]),
+            ("bb22", &["dd44"]),
+            ("dd44", &[]),
+        ];
+
+        let short = run(&history[..2]);
+        let full = run(history);
+
+        for (a, b) in short.iter().zip(&full) {
+            let colors = |info: &GraphInfo| info.lines.iter().map(|l| l.color).collect::<Vec<_>>();
+            assert_eq!(colors(a)[..], colors(b)[..colors(a).len()]);
+        }
+    }
+
+    #[test]
+    fn calculate_lanes_fills_in_commits() {
+        let mut commits = vec![commit("B", &["A"]), commit("X", &["A"]), commit("A", &[])];
+
+        calculate_lanes(&mut commits);
+
+        assert_eq!(
+            commits.iter().map(|c| c.lane).collect::<Vec<_>>(),
+            vec![0, 1, 0]
+        );
+        assert!(!commits[2].lines.is_empty());
+    }
+}
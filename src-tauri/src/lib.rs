@@ -1,197 +1,1309 @@
 mod git_ops;
+mod graph;
 
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
-use std::sync::mpsc::channel;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, sync_channel, RecvTimeoutError, SyncSender, TryRecvError};
+use std::sync::{Mutex, OnceLock};
 use std::time::Duration;
 use tauri::{Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
 
 #[tauri::command]
-async fn clone_repository(url: String, path: String) -> Result<String, String> {
+async fn clone_repository(
+    url: String,
+    path: String,
+    options: Option<git_ops::CloneOptions>,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<String, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let auth = auth.unwrap_or_default();
+        let options = options.unwrap_or_default();
+        git_ops::clone_repository(&url, &path, &auth, &options, None)?;
+        Ok(format!("Repository cloned to: {}", path))
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+async fn clone_repository_with_progress(
+    window: tauri::Window,
+    url: String,
+    path: String,
+    options: Option<git_ops::CloneOptions>,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<String, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let auth = auth.unwrap_or_default();
+        let options = options.unwrap_or_default();
+        let progress = Box::new(move |progress: git_ops::CloneProgress| {
+            let _ = window.emit("clone-progress", progress);
+        });
+        git_ops::clone_repository(&url, &path, &auth, &options, Some(progress))?;
+        Ok(format!("Repository cloned to: {}", path))
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+async fn ls_remote(url: String) -> Result<Vec<git_ops::RemoteRef>, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || git_ops::ls_remote(&url))
+        .await
+        .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+fn set_ssh_credentials(
+    key_path: String,
+    passphrase: Option<String>,
+) -> Result<(), git_ops::GitError> {
+    git_ops::set_ssh_key_path(&key_path, passphrase.as_deref())
+}
+
+#[tauri::command]
+fn is_git_repository(path: String) -> bool {
+    git_ops::is_git_repository(&path)
+}
+
+#[tauri::command]
+fn init_repository(
+    path: String,
+    bare: bool,
+    initial_branch: Option<String>,
+) -> Result<String, git_ops::GitError> {
+    git_ops::init_repository(&path, bare, initial_branch.as_deref())?;
+    let resolved =
+        std::fs::canonicalize(&path).map_err(|e| git_ops::GitError::Unknown(e.to_string()))?;
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn open_repository(path: String) -> Result<String, git_ops::GitError> {
+    git_ops::open_repository(&path)?;
+    Ok(format!("Repository opened: {}", path))
+}
+
+#[tauri::command]
+fn get_repository_info(path: String) -> Result<git_ops::RepositoryInfo, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_repository_info(&repo)
+}
+
+#[tauri::command]
+fn get_head_info(path: String) -> Result<git_ops::HeadInfo, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_head_info(&repo)
+}
+
+#[tauri::command]
+fn get_repository_state(path: String) -> Result<git_ops::RepoState, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_repository_state(&repo)
+}
+
+#[tauri::command]
+fn get_repository_size(path: String) -> Result<git_ops::RepoSize, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_repository_size(&repo)
+}
+
+#[tauri::command]
+async fn run_gc(path: String, aggressive: bool) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::run_gc(&repo, aggressive)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+fn count_objects(path: String) -> Result<git_ops::ObjectCount, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::count_objects(&repo)
+}
+
+#[tauri::command]
+fn get_pack_info(path: String) -> Result<Vec<git_ops::PackInfo>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_pack_info(&repo)
+}
+
+#[tauri::command]
+async fn verify_repository(path: String) -> Result<git_ops::FsckResult, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::verify_repository(&repo)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+fn get_archive(
+    path: String,
+    tree_ish: String,
+    format: String,
+    output_path: String,
+) -> Result<String, git_ops::GitError> {
+    let format: git_ops::ArchiveFormat = format.parse()?;
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_archive(&repo, &tree_ish, format, &output_path)?;
+    let resolved = std::fs::canonicalize(&output_path)
+        .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?;
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn get_branches(path: String) -> Result<Vec<git_ops::GitBranch>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_branches(&repo)
+}
+
+#[tauri::command]
+fn get_ahead_behind(
+    path: String,
+    local: String,
+    upstream: String,
+) -> Result<(usize, usize), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_ahead_behind(&repo, &local, &upstream)
+}
+
+#[tauri::command]
+fn get_tracking_branch(
+    path: String,
+    branch_name: String,
+) -> Result<Option<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_tracking_branch(&repo, &branch_name)
+}
+
+#[tauri::command]
+fn set_tracking_branch(
+    path: String,
+    branch_name: String,
+    remote: String,
+    remote_branch: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::set_tracking_branch(&repo, &branch_name, &remote, &remote_branch)
+}
+
+#[tauri::command]
+fn unset_tracking_branch(path: String, branch_name: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::unset_tracking_branch(&repo, &branch_name)
+}
+
+#[tauri::command]
+fn get_merge_base(
+    path: String,
+    commit_a: String,
+    commit_b: String,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_merge_base(&repo, &commit_a, &commit_b)
+}
+
+#[tauri::command]
+fn get_merge_base_many(path: String, commits: Vec<String>) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    let commits = commits.iter().map(|c| c.as_str()).collect();
+    git_ops::get_merge_base_many(&repo, commits)
+}
+
+#[tauri::command]
+fn create_branch(
+    path: String,
+    name: String,
+    start_point: Option<String>,
+) -> Result<git_ops::GitBranch, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::create_branch(&repo, &name, start_point.as_deref())
+}
+
+#[tauri::command]
+fn delete_branch(path: String, name: String, force: bool) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::delete_branch(&repo, &name, force)
+}
+
+#[tauri::command]
+fn rename_branch(
+    path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::rename_branch(&repo, &old_name, &new_name)
+}
+
+#[tauri::command]
+fn get_remotes(path: String) -> Result<Vec<git_ops::GitRemote>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_remotes(&repo)
+}
+
+#[tauri::command]
+fn add_remote(path: String, name: String, url: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::add_remote(&repo, &name, &url)
+}
+
+#[tauri::command]
+fn remove_remote(path: String, name: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::remove_remote(&repo, &name)
+}
+
+#[tauri::command]
+fn rename_remote(
+    path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::rename_remote(&repo, &old_name, &new_name)
+}
+
+#[tauri::command]
+fn set_remote_url(path: String, name: String, url: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::set_remote_url(&repo, &name, &url)
+}
+
+#[tauri::command]
+fn branch_contains_commit(
+    path: String,
+    branch_name: String,
+    commit_id: String,
+) -> Result<bool, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::branch_contains_commit(&repo, &branch_name, &commit_id)
+}
+
+#[tauri::command]
+fn get_branches_containing(
+    path: String,
+    commit_id: String,
+    remote: Option<bool>,
+) -> Result<Vec<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_branches_containing(&repo, &commit_id, remote.unwrap_or(true))
+}
+
+#[tauri::command]
+fn get_remote_branches(
+    path: String,
+    remote_name: String,
+) -> Result<Vec<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_remote_branches(&repo, &remote_name)
+}
+
+#[tauri::command]
+fn get_remote_refs_cached(
+    path: String,
+    remote_name: String,
+) -> Result<Vec<git_ops::RemoteRef>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_remote_refs_cached(&repo, &remote_name)
+}
+
+#[tauri::command]
+fn update_ref(
+    path: String,
+    ref_name: String,
+    target_oid: String,
+    force: bool,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::update_ref(&repo, &ref_name, &target_oid, force)
+}
+
+#[tauri::command]
+fn read_symbolic_ref(path: String, ref_name: String) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::read_symbolic_ref(&repo, &ref_name)
+}
+
+#[tauri::command]
+fn set_symbolic_ref(
+    path: String,
+    ref_name: String,
+    target_ref: String,
+    force: bool,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::set_symbolic_ref(&repo, &ref_name, &target_ref, force)
+}
+
+#[tauri::command]
+fn get_tags(path: String) -> Result<Vec<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_tags(&repo)
+}
+
+#[tauri::command]
+fn get_tag_commit(path: String, tag_name: String) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_tag_commit(&repo, &tag_name)
+}
+
+#[tauri::command]
+fn get_tags_containing(path: String, commit_id: String) -> Result<Vec<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_tags_containing(&repo, &commit_id)
+}
+
+#[tauri::command]
+fn get_annotated_tag_info(
+    path: String,
+    tag_name: String,
+) -> Result<git_ops::TagInfo, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_annotated_tag_info(&repo, &tag_name)
+}
+
+#[tauri::command]
+fn describe_commit(
+    path: String,
+    commit_id: String,
+    tags: bool,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::describe_commit(&repo, &commit_id, tags)
+}
+
+#[tauri::command]
+fn get_notes(
+    path: String,
+    commit_id: String,
+    notes_ref: Option<String>,
+) -> Result<Option<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_notes(&repo, &commit_id, notes_ref.as_deref())
+}
+
+#[tauri::command]
+fn add_note(
+    path: String,
+    commit_id: String,
+    message: String,
+    notes_ref: Option<String>,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::add_note(&repo, &commit_id, &message, notes_ref.as_deref())
+}
+
+#[tauri::command]
+fn remove_note(
+    path: String,
+    commit_id: String,
+    notes_ref: Option<String>,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::remove_note(&repo, &commit_id, notes_ref.as_deref())
+}
+
+#[tauri::command]
+fn create_tag(
+    path: String,
+    name: String,
+    target: String,
+    message: Option<String>,
+    tagger: Option<git_ops::GitSignature>,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::create_tag(&repo, &name, &target, message.as_deref(), tagger)
+}
+
+#[tauri::command]
+fn delete_tag(path: String, name: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::delete_tag(&repo, &name)
+}
+
+#[tauri::command]
+fn get_submodules(path: String) -> Result<Vec<git_ops::GitSubmodule>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_submodules(&repo)
+}
+
+#[tauri::command]
+fn get_submodule_status(
+    path: String,
+) -> Result<Vec<git_ops::GitSubmoduleStatus>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_submodule_status(&repo)
+}
+
+#[tauri::command]
+async fn add_submodule(
+    path: String,
+    url: String,
+    submodule_path: String,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::add_submodule(&repo, &url, &submodule_path)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+async fn init_submodule(path: String, name: String) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::init_submodule(&repo, &name)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+async fn update_submodule(
+    path: String,
+    name: String,
+    recursive: bool,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::update_submodule(&repo, &name, recursive)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+async fn deinit_submodule(
+    path: String,
+    name: String,
+    force: bool,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::deinit_submodule(&repo, &name, force)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
+}
+
+#[tauri::command]
+fn get_worktrees(path: String) -> Result<Vec<git_ops::GitWorktree>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_worktrees(&repo)
+}
+
+#[tauri::command]
+fn add_worktree(
+    path: String,
+    worktree_path: String,
+    branch: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::add_worktree(&repo, &worktree_path, &branch)
+}
+
+#[tauri::command]
+fn remove_worktree(path: String, name: String, force: bool) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::remove_worktree(&repo, &name, force)
+}
+
+#[tauri::command]
+fn get_config(path: String, key: String) -> Result<Option<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_config(&repo, &key)
+}
+
+#[tauri::command]
+fn set_config(
+    path: String,
+    key: String,
+    value: String,
+    level: String,
+) -> Result<(), git_ops::GitError> {
+    let level: git_ops::ConfigLevel = level.parse()?;
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::set_config(&repo, &key, &value, level)
+}
+
+#[tauri::command]
+fn get_config_entries(
+    path: String,
+    glob: Option<String>,
+    level: Option<String>,
+) -> Result<Vec<git_ops::ConfigEntry>, git_ops::GitError> {
+    let level = level
+        .map(|level| level.parse::<git_ops::ConfigLevel>())
+        .transpose()?;
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_config_entries(&repo, glob.as_deref(), level)
+}
+
+const WATCH_STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Serialize, Clone)]
+struct RepoChangedEvent {
+    paths: Vec<String>,
+}
+
+type WatcherEntry = (u64, SyncSender<()>);
+
+// Shutdown senders for running watchers, keyed by repo path and tagged with a
+// per-watcher id. Dropping a sender also stops its watcher thread.
+fn watchers() -> &'static Mutex<HashMap<String, WatcherEntry>> {
+    static WATCHERS: OnceLock<Mutex<HashMap<String, WatcherEntry>>> = OnceLock::new();
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// With an id, only stops the watcher if it hasn't since been replaced
+fn stop_watcher(repo_path: &str, id: Option<u64>) -> bool {
+    let Ok(mut watchers) = watchers().lock() else {
+        return false;
+    };
+    if matches!((watchers.get(repo_path), id), (Some((current, _)), Some(id)) if *current != id) {
+        return false;
+    }
+    let sender = watchers.remove(repo_path);
+    match sender {
+        Some((_, sender)) => {
+            let _ = sender.try_send(());
+            true
+        }
+        None => false,
+    }
+}
+
+#[tauri::command]
+fn watch_repo(
+    window: tauri::Window,
+    repo_path: String,
+    poll_ms: Option<u64>,
+    debounce_ms: Option<u64>,
+) -> Result<u64, String> {
+    let poll_interval = Duration::from_millis(poll_ms.unwrap_or(2000));
+    let debounce = Duration::from_millis(debounce_ms.unwrap_or(300));
+    let (tx, rx) = channel::<RepoChangedEvent>();
+
+    let mut watcher = RecommendedWatcher::new(
+        move |res: Result<notify::Event, notify::Error>| {
+            if let Ok(event) = res {
+                // Events without path granularity fall back to an empty list
+                let paths = if matches!(event.kind, notify::EventKind::Any) {
+                    Vec::new()
+                } else {
+                    event
+                        .paths
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .collect()
+                };
+                let _ = tx.send(RepoChangedEvent { paths });
+            }
+        },
+        Config::default().with_poll_interval(poll_interval),
+    )
+    .map_err(|e| e.to_string())?;
+
+    watcher
+        .watch(Path::new(&repo_path), RecursiveMode::Recursive)
+        .map_err(|e| e.to_string())?;
+
+    static NEXT_WATCHER_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_WATCHER_ID.fetch_add(1, Ordering::Relaxed);
+
+    let (stop_tx, stop_rx) = sync_channel::<()>(1);
+    // Replacing an existing entry drops its sender, stopping the old watcher
+    watchers()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(repo_path.clone(), (id, stop_tx));
+
+    // Another window may have taken over this path by the time this one closes
+    let destroyed_path = repo_path.clone();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Destroyed = event {
+            stop_watcher(&destroyed_path, Some(id));
+        }
+    });
+
+    std::thread::spawn(move || {
+        let _watcher = watcher;
+        let stop_requested =
+            || matches!(stop_rx.try_recv(), Ok(()) | Err(TryRecvError::Disconnected));
+
+        loop {
+            if stop_requested() {
+                return;
+            }
+            let mut event = match rx.recv_timeout(WATCH_STOP_CHECK_INTERVAL) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+
+            // Coalesce bursts of events until things have been quiet for `debounce`.
+            // An event without paths means "anything may have changed" and wins.
+            let mut unknown_paths = event.paths.is_empty();
+            loop {
+                match rx.recv_timeout(debounce) {
+                    Ok(next) => {
+                        unknown_paths |= next.paths.is_empty();
+                        for path in next.paths {
+                            if !event.paths.contains(&path) {
+                                event.paths.push(path);
+                            }
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if unknown_paths {
+                event.paths.clear();
+            }
+            if stop_requested() {
+                return;
+            }
+            // The branch may have changed, e.g. after a checkout
+            let _ = window.set_title(&repo_window_title(&repo_path));
+            let _ = window.emit("repo-changed", event);
+        }
+    });
+
+    Ok(id)
+}
+
+#[tauri::command]
+fn stop_watching(path: String, id: Option<u64>) -> Result<(), String> {
+    if stop_watcher(&path, id) {
+        Ok(())
+    } else {
+        Err(format!("No watcher running for {}", path))
+    }
+}
+
+#[tauri::command]
+fn get_status(
+    path: String,
+    rename_threshold: Option<u32>,
+) -> Result<Vec<git_ops::GitFileStatus>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_status(&repo, rename_threshold)
+}
+
+#[tauri::command]
+fn get_diff(
+    path: String,
+    file_path: String,
+    staged: bool,
+    rename_threshold: Option<u32>,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff(&repo, &file_path, staged, rename_threshold)
+}
+
+#[tauri::command]
+fn get_diff_cached(path: String, file_path: String) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff_cached(&repo, &file_path)
+}
+
+#[tauri::command]
+fn get_diff_with_rename(
+    path: String,
+    file_path: String,
+    staged: bool,
+    rename_threshold: u32,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff(&repo, &file_path, staged, Some(rename_threshold))
+}
+
+#[tauri::command]
+fn get_diff_structured(
+    path: String,
+    file_path: String,
+    staged: bool,
+) -> Result<Vec<git_ops::DiffHunk>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff_structured(&repo, &file_path, staged)
+}
+
+#[tauri::command]
+fn get_diff_stat(path: String, staged: bool) -> Result<Vec<git_ops::DiffStat>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff_stat(&repo, staged)
+}
+
+#[tauri::command]
+fn get_index_entries(path: String) -> Result<Vec<git_ops::IndexEntry>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_index_entries(&repo)
+}
+
+#[tauri::command]
+fn get_diff_with_options(
+    path: String,
+    file_path: String,
+    staged: bool,
+    opts: git_ops::DiffViewOptions,
+) -> Result<git_ops::DiffHunkView, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff_with_options(&repo, &file_path, staged, opts)
+}
+
+#[tauri::command]
+fn unstage_file(path: String, file_path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::unstage_file(&repo, &file_path)
+}
+
+#[tauri::command]
+fn stage_lines(
+    path: String,
+    file_path: String,
+    line_ranges: Vec<[u32; 2]>,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    let line_ranges = line_ranges
+        .into_iter()
+        .map(|[start, end]| (start, end))
+        .collect();
+    git_ops::stage_lines(&repo, &file_path, line_ranges)
+}
+
+#[tauri::command]
+fn discard_lines(
+    path: String,
+    file_path: String,
+    line_ranges: Vec<[u32; 2]>,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    let line_ranges = line_ranges
+        .into_iter()
+        .map(|[start, end]| (start, end))
+        .collect();
+    git_ops::discard_lines(&repo, &file_path, line_ranges)
+}
+
+#[tauri::command]
+fn unstage_hunk(
+    path: String,
+    file_path: String,
+    full_diff: String,
+    hunk_header: String,
+    hunk_lines: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::unstage_hunk(&repo, &file_path, &full_diff, &hunk_header, &hunk_lines)
+}
+
+#[tauri::command]
+fn stage_hunk(
+    path: String,
+    file_path: String,
+    full_diff: String,
+    hunk_header: String,
+    hunk_lines: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::stage_hunk(&repo, &file_path, &full_diff, &hunk_header, &hunk_lines)
+}
+
+#[tauri::command]
+fn stage_file(path: String, file_path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::stage_file(&repo, &file_path)
+}
+
+#[tauri::command]
+fn bulk_stage_files(path: String, file_paths: Vec<String>) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    let file_paths: Vec<&str> = file_paths.iter().map(|p| p.as_str()).collect();
+    git_ops::bulk_stage_files(&repo, &file_paths)
+}
+
+#[tauri::command]
+fn stage_all(path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::stage_all(&repo)
+}
+
+#[tauri::command]
+fn unstage_all(path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::unstage_all(&repo)
+}
+
+#[tauri::command]
+fn discard_all(path: String, remove_untracked: bool) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::discard_all(&repo, remove_untracked)
+}
+
+#[tauri::command]
+fn bulk_unstage_files(path: String, file_paths: Vec<String>) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    let file_paths: Vec<&str> = file_paths.iter().map(|p| p.as_str()).collect();
+    git_ops::bulk_unstage_files(&repo, &file_paths)
+}
+
+#[tauri::command]
+fn discard_file(path: String, file_path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::discard_file(&repo, &file_path)
+}
+
+#[tauri::command]
+fn discard_hunk(
+    path: String,
+    file_path: String,
+    full_diff: String,
+    hunk_header: String,
+    hunk_lines: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::discard_hunk(&repo, &file_path, &full_diff, &hunk_header, &hunk_lines)
+}
+
+#[tauri::command]
+fn ignore_file(path: String, file_path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::ignore_file(&repo, &file_path)
+}
+
+#[tauri::command]
+fn get_ignored_files(path: String, dir: String) -> Result<Vec<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_ignored_files(&repo, &dir)
+}
+
+#[tauri::command]
+fn check_ignore(path: String, file_path: String) -> Result<bool, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::check_ignore(&repo, &file_path)
+}
+
+#[tauri::command]
+fn add_gitignore_pattern(
+    path: String,
+    pattern: String,
+    scope_dir: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::add_gitignore_pattern(&repo, &pattern, &scope_dir)
+}
+
+#[tauri::command]
+fn remove_gitignore_pattern(
+    path: String,
+    pattern: String,
+    scope_dir: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::remove_gitignore_pattern(&repo, &pattern, &scope_dir)
+}
+
+#[tauri::command]
+fn get_gitattributes(path: String) -> Result<Vec<git_ops::GitAttribute>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_gitattributes(&repo)
+}
+
+#[tauri::command]
+fn check_attribute(
+    path: String,
+    file_path: String,
+    attr: String,
+) -> Result<Option<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::check_attribute(&repo, &file_path, &attr)
+}
+
+#[tauri::command]
+fn commit_changes(
+    path: String,
+    message: String,
+    author_name: Option<String>,
+    author_email: Option<String>,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::commit_changes(
+        &repo,
+        &message,
+        author_name.as_deref(),
+        author_email.as_deref(),
+    )
+}
+
+#[tauri::command]
+fn amend_commit(path: String, message: Option<String>) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::amend_commit(&repo, message.as_deref(), None, false)
+}
+
+#[tauri::command]
+fn squash_commits(
+    path: String,
+    base_commit: String,
+    message: Option<String>,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::squash_commits(&repo, &base_commit, message.as_deref())
+}
+
+#[tauri::command]
+fn reset_to_commit(path: String, commit_id: String, mode: String) -> Result<(), git_ops::GitError> {
+    let mode: git_ops::ResetMode = mode.parse()?;
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::reset_to_commit(&repo, &commit_id, mode)
+}
+
+#[tauri::command]
+fn checkout_branch(path: String, branch_name: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::checkout_branch(&repo, &branch_name)
+}
+
+#[tauri::command]
+async fn fetch_remote(
+    path: String,
+    remote_name: String,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<(), git_ops::GitError> {
     tokio::task::spawn_blocking(move || {
-        git_ops::clone_repository(&url, &path).map_err(|e| e.to_string())?;
-        Ok(format!("Repository cloned to: {}", path))
+        let repo = git_ops::open_repository(&path)?;
+        let auth = auth.unwrap_or_default();
+        git_ops::fetch_remote(&repo, &remote_name, &auth)
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn is_git_repository(path: String) -> bool {
-    git_ops::is_git_repository(&path)
+async fn fetch_all_remotes(
+    path: String,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<Vec<String>, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        let auth = auth.unwrap_or_default();
+        git_ops::fetch_all_remotes(&repo, &auth)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn open_repository(path: String) -> Result<String, String> {
-    match git_ops::open_repository(&path) {
-        Ok(_) => Ok(format!("Repository opened: {}", path)),
-        Err(e) => Err(format!("Failed to open repository: {}", e)),
-    }
+async fn fetch_multiple_repos(
+    repos: Vec<String>,
+    remote_name: String,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<Vec<git_ops::FetchResult>, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let auth = auth.unwrap_or_default();
+        // One repository failing shouldn't stop the others from refreshing
+        repos
+            .into_iter()
+            .map(|path| {
+                let result = git_ops::open_repository(&path)
+                    .and_then(|repo| git_ops::fetch_remote(&repo, &remote_name, &auth));
+                git_ops::FetchResult {
+                    path,
+                    success: result.is_ok(),
+                    error: result.err().map(|e| e.to_string()),
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))
 }
 
 #[tauri::command]
-fn get_branches(path: String) -> Result<Vec<git_ops::GitBranch>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_branches(&repo).map_err(|e| e.to_string())
+async fn prune_remote(path: String, remote_name: String) -> Result<Vec<String>, git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::prune_remote(&repo, &remote_name)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn get_remotes(path: String) -> Result<Vec<git_ops::GitRemote>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_remotes(&repo).map_err(|e| e.to_string())
+async fn pull_remote(
+    path: String,
+    remote_name: String,
+    rebase: Option<bool>,
+    ff_only: Option<bool>,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        let auth = auth.unwrap_or_default();
+        git_ops::pull_remote(
+            &repo,
+            &remote_name,
+            rebase.unwrap_or(false),
+            ff_only.unwrap_or(false),
+            &auth,
+        )
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn get_remote_branches(path: String, remote_name: String) -> Result<Vec<String>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_remote_branches(&repo, &remote_name).map_err(|e| e.to_string())
+async fn push_remote(
+    path: String,
+    remote_name: String,
+    branch_name: Option<String>,
+    force: bool,
+    force_with_lease: bool,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        let auth = auth.unwrap_or_default();
+        git_ops::push_remote(
+            &repo,
+            &remote_name,
+            branch_name.as_deref(),
+            force,
+            force_with_lease,
+            &auth,
+        )
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn get_tags(path: String) -> Result<Vec<String>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_tags(&repo).map_err(|e| e.to_string())
+async fn push_tag(
+    path: String,
+    remote_name: String,
+    tag_name: String,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        let auth = auth.unwrap_or_default();
+        git_ops::push_tag(&repo, &remote_name, &tag_name, &auth)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn get_tag_commit(path: String, tag_name: String) -> Result<String, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_tag_commit(&repo, &tag_name).map_err(|e| e.to_string())
+async fn delete_remote_tag(
+    path: String,
+    remote_name: String,
+    tag_name: String,
+    auth: Option<git_ops::AuthConfig>,
+) -> Result<(), git_ops::GitError> {
+    tokio::task::spawn_blocking(move || {
+        let repo = git_ops::open_repository(&path)?;
+        let auth = auth.unwrap_or_default();
+        git_ops::delete_remote_tag(&repo, &remote_name, &tag_name, &auth)
+    })
+    .await
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-fn get_submodules(path: String) -> Result<Vec<git_ops::GitSubmodule>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_submodules(&repo).map_err(|e| e.to_string())
+fn merge_branch(
+    path: String,
+    branch_name: String,
+    no_ff: bool,
+    squash: bool,
+) -> Result<git_ops::MergeResult, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::merge_branch(&repo, &branch_name, no_ff, squash)
 }
 
 #[tauri::command]
-fn watch_repo(window: tauri::Window, repo_path: String) -> Result<(), String> {
-    let (tx, rx) = channel();
-
-    let mut watcher = RecommendedWatcher::new(
-        move |res: Result<notify::Event, notify::Error>| {
-            if res.is_ok() {
-                let _ = tx.send(());
-            }
-        },
-        Config::default().with_poll_interval(Duration::from_secs(2)),
-    )
-    .map_err(|e| e.to_string())?;
+fn is_merge_in_progress(path: String) -> Result<bool, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    Ok(git_ops::is_merge_in_progress(&repo))
+}
 
-    watcher
-        .watch(Path::new(&repo_path), RecursiveMode::Recursive)
-        .map_err(|e| e.to_string())?;
+#[tauri::command]
+fn get_merge_message(path: String) -> Result<Option<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_merge_message(&repo)
+}
 
-    std::thread::spawn(move || {
-        let _watcher = watcher;
-        while rx.recv().is_ok() {
-            let _ = window.emit("repo-changed", ());
-        }
-    });
+#[tauri::command]
+fn abort_merge(path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::abort_merge(&repo)
+}
 
-    Ok(())
+#[tauri::command]
+fn get_conflict_files(path: String) -> Result<Vec<git_ops::ConflictEntry>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_conflict_files(&repo)
 }
 
 #[tauri::command]
-fn get_status(path: String) -> Result<Vec<git_ops::GitFileStatus>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_status(&repo).map_err(|e| e.to_string())
+fn get_conflict_versions(
+    path: String,
+    file_path: String,
+) -> Result<git_ops::ConflictVersions, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_conflict_versions(&repo, &file_path)
 }
 
 #[tauri::command]
-fn get_diff(path: String, file_path: String, staged: bool) -> Result<String, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_diff(&repo, &file_path, staged).map_err(|e| e.to_string())
+fn mark_conflict_resolved(path: String, file_path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::mark_conflict_resolved(&repo, &file_path)
 }
 
 #[tauri::command]
-fn unstage_file(path: String, file_path: String) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::unstage_file(&repo, &file_path).map_err(|e| e.to_string())
+fn is_rebase_in_progress(path: String) -> Result<bool, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    Ok(git_ops::is_rebase_in_progress(&repo))
 }
 
 #[tauri::command]
-fn unstage_hunk(
+fn rebase_branch(
     path: String,
-    file_path: String,
-    full_diff: String,
-    hunk_header: String,
-    hunk_lines: String,
-) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::unstage_hunk(&repo, &file_path, &full_diff, &hunk_header, &hunk_lines)
-        .map_err(|e| e.to_string())
+    branch: String,
+    onto: String,
+) -> Result<git_ops::RebaseStatus, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::start_rebase(&repo, &branch, &onto)
 }
 
 #[tauri::command]
-fn stage_hunk(
+fn continue_rebase(
     path: String,
-    file_path: String,
-    full_diff: String,
-    hunk_header: String,
-    hunk_lines: String,
-) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::stage_hunk(&repo, &file_path, &full_diff, &hunk_header, &hunk_lines)
-        .map_err(|e| e.to_string())
+    commit_message: String,
+) -> Result<git_ops::RebaseStatus, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::continue_rebase(&repo, &commit_message)
 }
 
 #[tauri::command]
-fn stage_file(path: String, file_path: String) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::stage_file(&repo, &file_path).map_err(|e| e.to_string())
+fn skip_rebase_step(path: String) -> Result<git_ops::RebaseStatus, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::skip_rebase_step(&repo)
 }
 
 #[tauri::command]
-fn discard_file(path: String, file_path: String) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::discard_file(&repo, &file_path).map_err(|e| e.to_string())
+fn abort_rebase(path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::abort_rebase(&repo)
 }
 
 #[tauri::command]
-fn discard_hunk(
+fn cherry_pick_commit(
     path: String,
-    file_path: String,
-    full_diff: String,
-    hunk_header: String,
-    hunk_lines: String,
-) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::discard_hunk(&repo, &file_path, &full_diff, &hunk_header, &hunk_lines)
-        .map_err(|e| e.to_string())
+    commit_id: String,
+    mainline: Option<usize>,
+) -> Result<git_ops::CherryPickResult, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::cherry_pick_commit(&repo, &commit_id, mainline)
 }
 
 #[tauri::command]
-fn ignore_file(path: String, file_path: String) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::ignore_file(&repo, &file_path).map_err(|e| e.to_string())
+fn cherry_pick_range(
+    path: String,
+    from: String,
+    to: String,
+) -> Result<git_ops::CherryPickRangeResult, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::cherry_pick_range(&repo, &from, &to)
 }
 
 #[tauri::command]
-fn checkout_branch(path: String, branch_name: String) -> Result<(), String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::checkout_branch(&repo, &branch_name).map_err(|e| e.to_string())
+fn abort_cherry_pick(path: String) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::abort_cherry_pick(&repo)
 }
 
 #[tauri::command]
-async fn fetch_remote(path: String, remote_name: String) -> Result<(), String> {
+async fn revert_commit(
+    path: String,
+    commit_id: String,
+    mainline: Option<usize>,
+    auto_commit: bool,
+) -> Result<git_ops::RevertResult, git_ops::GitError> {
     tokio::task::spawn_blocking(move || {
-        let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-        git_ops::fetch_remote(&repo, &remote_name).map_err(|e| e.to_string())
+        let repo = git_ops::open_repository(&path)?;
+        git_ops::revert_commit(&repo, &commit_id, mainline, auto_commit)
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(|e| git_ops::GitError::Unknown(e.to_string()))?
 }
 
 #[tauri::command]
-async fn pull_remote(path: String, remote_name: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-        git_ops::pull_remote(&repo, &remote_name).map_err(|e| e.to_string())
-    })
-    .await
-    .map_err(|e| e.to_string())?
+fn create_stash(
+    path: String,
+    message: Option<String>,
+    include_untracked: bool,
+    keep_index: bool,
+) -> Result<Option<String>, git_ops::GitError> {
+    let mut repo = git_ops::open_repository(&path)?;
+    git_ops::create_stash(&mut repo, message.as_deref(), include_untracked, keep_index)
+        .map(|oid| oid.map(|oid| oid.to_string()))
+}
+
+#[tauri::command]
+fn list_stashes(path: String) -> Result<Vec<git_ops::GitStash>, git_ops::GitError> {
+    let mut repo = git_ops::open_repository(&path)?;
+    git_ops::list_stashes(&mut repo)
+}
+
+#[tauri::command]
+fn apply_stash(path: String, index: usize, reinstate_index: bool) -> Result<(), git_ops::GitError> {
+    let mut repo = git_ops::open_repository(&path)?;
+    git_ops::apply_stash(&mut repo, index, reinstate_index)
+}
+
+#[tauri::command]
+fn pop_stash(path: String, index: usize) -> Result<(), git_ops::GitError> {
+    let mut repo = git_ops::open_repository(&path)?;
+    git_ops::pop_stash(&mut repo, index)
+}
+
+#[tauri::command]
+fn drop_stash(path: String, index: usize) -> Result<(), git_ops::GitError> {
+    let mut repo = git_ops::open_repository(&path)?;
+    git_ops::drop_stash(&mut repo, index)
+}
+
+#[tauri::command]
+fn get_stash_diff(
+    path: String,
+    index: usize,
+) -> Result<Vec<git_ops::CommitFile>, git_ops::GitError> {
+    let mut repo = git_ops::open_repository(&path)?;
+    git_ops::get_stash_diff(&mut repo, index)
+}
+
+#[tauri::command]
+fn get_reflog(
+    path: String,
+    ref_name: String,
+) -> Result<Vec<git_ops::ReflogEntry>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    let ref_name = if ref_name.is_empty() {
+        "HEAD"
+    } else {
+        ref_name.as_str()
+    };
+    git_ops::get_reflog(&repo, ref_name)
 }
 
 #[tauri::command]
@@ -200,27 +1312,252 @@ fn get_commits(
     limit: usize,
     local_only: Option<bool>,
     branch_name: Option<String>,
-) -> Result<Vec<git_ops::GitCommit>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
+    after: Option<String>,
+    first_parent_only: Option<bool>,
+) -> Result<git_ops::CommitPage, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
     git_ops::get_commits(
         &repo,
         limit,
         local_only.unwrap_or(false),
         branch_name.as_deref(),
+        after.as_deref(),
+        first_parent_only.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+fn get_file_history(
+    path: String,
+    file_path: String,
+    limit: usize,
+) -> Result<Vec<git_ops::GitCommit>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_file_history(&repo, &file_path, limit)
+}
+
+#[tauri::command]
+fn get_commits_touching_file(
+    path: String,
+    file_path: String,
+    limit: usize,
+    follow_renames: bool,
+) -> Result<Vec<git_ops::GitCommit>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_commits_touching_file(&repo, &file_path, limit, follow_renames)
+}
+
+#[tauri::command]
+fn search_commits(
+    path: String,
+    query: String,
+    search_in: String,
+    limit: usize,
+) -> Result<Vec<git_ops::GitCommit>, git_ops::GitError> {
+    let search_in: git_ops::SearchIn = search_in.parse()?;
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::search_commits(&repo, &query, search_in, limit)
+}
+
+#[tauri::command]
+fn git_grep(
+    path: String,
+    pattern: String,
+    commit_id: Option<String>,
+    case_insensitive: bool,
+    word_match: bool,
+) -> Result<git_ops::GrepResult, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::git_grep(
+        &repo,
+        &pattern,
+        commit_id.as_deref(),
+        case_insensitive,
+        word_match,
+    )
+}
+
+#[tauri::command]
+fn get_file_at_commit(
+    path: String,
+    commit_id: String,
+    file_path: String,
+) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_file_at_commit(&repo, &commit_id, &file_path)
+}
+
+#[tauri::command]
+fn checkout_file(
+    path: String,
+    file_path: String,
+    commit_id: String,
+) -> Result<(), git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::checkout_file(&repo, &file_path, &commit_id)
+}
+
+#[tauri::command]
+fn get_tree_entries(
+    path: String,
+    commit_id: String,
+    tree_path: String,
+) -> Result<Vec<git_ops::TreeEntry>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_tree_entries(&repo, &commit_id, &tree_path)
+}
+
+#[tauri::command]
+fn get_blame(
+    path: String,
+    file_path: String,
+    commit_id: Option<String>,
+) -> Result<Vec<git_ops::GitBlameLine>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_blame(&repo, &file_path, commit_id.as_deref())
+}
+
+#[tauri::command]
+fn get_blame_range(
+    path: String,
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+    commit_id: Option<String>,
+) -> Result<Vec<git_ops::GitBlameLine>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_blame_range(
+        &repo,
+        &file_path,
+        start_line,
+        end_line,
+        commit_id.as_deref(),
     )
-    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn get_branch_head(path: String, branch_name: String) -> Result<String, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_branch_head(&repo, &branch_name).map_err(|e| e.to_string())
+fn get_commits_between(
+    path: String,
+    exclude: String,
+    include: String,
+    limit: usize,
+) -> Result<Vec<git_ops::GitCommit>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_commits_between(&repo, &exclude, &include, limit)
+}
+
+#[tauri::command]
+fn compare_branches(
+    path: String,
+    base: String,
+    compare: String,
+    limit: usize,
+) -> Result<git_ops::BranchComparison, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::compare_branches(&repo, &base, &compare, limit)
+}
+
+#[tauri::command]
+fn get_graph_only(
+    path: String,
+    commit_ids: Vec<String>,
+) -> Result<Vec<git_ops::GraphPosition>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_graph_only(&repo, commit_ids)
+}
+
+#[tauri::command]
+fn get_commits_by_author(
+    path: String,
+    author: String,
+    limit: usize,
+    branch_name: Option<String>,
+) -> Result<Vec<git_ops::GitCommit>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_commits_by_author(&repo, &author, limit, branch_name.as_deref())
+}
+
+#[tauri::command]
+fn get_commits_by_date_range(
+    path: String,
+    from_ts: i64,
+    to_ts: i64,
+    limit: usize,
+) -> Result<Vec<git_ops::GitCommit>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_commits_by_date_range(&repo, from_ts, to_ts, limit)
+}
+
+#[tauri::command]
+fn get_branch_head(path: String, branch_name: String) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_branch_head(&repo, &branch_name)
+}
+
+#[tauri::command]
+fn get_commit_diff(
+    path: String,
+    commit_id: String,
+    rename_threshold: Option<u32>,
+) -> Result<Vec<git_ops::CommitFile>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_commit_diff(&repo, &commit_id, rename_threshold)
+}
+
+#[tauri::command]
+fn get_patch_id(path: String, commit_id: String) -> Result<String, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_patch_id(&repo, &commit_id)
+}
+
+#[tauri::command]
+fn format_patch(
+    path: String,
+    range: String,
+    output_dir: String,
+) -> Result<Vec<String>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::format_patch(&repo, &range, &output_dir)
+}
+
+#[tauri::command]
+fn apply_patch(
+    path: String,
+    patch_path: String,
+    cached: bool,
+    three_way: bool,
+) -> Result<git_ops::ApplyResult, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::apply_patch(&repo, &patch_path, cached, three_way)
+}
+
+#[tauri::command]
+fn get_log_stats(
+    path: String,
+    commit_ids: Vec<String>,
+) -> Result<Vec<git_ops::GitCommitStat>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_log_stats(&repo, commit_ids)
+}
+
+#[tauri::command]
+fn get_commit_diff_with_parent(
+    path: String,
+    commit_id: String,
+    parent_index: usize,
+) -> Result<Vec<git_ops::CommitFile>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_commit_diff_with_parent(&repo, &commit_id, parent_index)
 }
 
 #[tauri::command]
-fn get_commit_diff(path: String, commit_id: String) -> Result<Vec<git_ops::CommitFile>, String> {
-    let repo = git_ops::open_repository(&path).map_err(|e| e.to_string())?;
-    git_ops::get_commit_diff(&repo, &commit_id).map_err(|e| e.to_string())
+fn get_diff_between_commits(
+    path: String,
+    from_id: String,
+    to_id: String,
+) -> Result<Vec<git_ops::CommitFile>, git_ops::GitError> {
+    let repo = git_ops::open_repository(&path)?;
+    git_ops::get_diff_between_commits(&repo, &from_id, &to_id)
 }
 
 #[tauri::command]
@@ -233,13 +1570,85 @@ fn open_repo_window(app: tauri::AppHandle, repo_path: String) -> Result<(), Stri
     }
 
     let url = format!("/?path={}", urlencoding::encode(&repo_path));
+    let title = repo_window_title(&repo_path);
+
+    WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title(&title)
+        .inner_size(1200.0, 800.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // Failing to record the repo shouldn't fail opening it
+    let _ = add_recent_repository(app, repo_path);
+
+    Ok(())
+}
+
+const MAX_RECENT_REPOSITORIES: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RecentRepository {
+    path: String,
+    name: String,
+    last_opened: i64,
+}
+
+fn recent_repositories_file(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join("recent_repositories.json"))
+}
+
+#[tauri::command]
+fn get_recent_repositories(app: tauri::AppHandle) -> Result<Vec<RecentRepository>, String> {
+    let file = recent_repositories_file(&app)?;
+    match std::fs::read_to_string(&file) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+fn add_recent_repository(app: tauri::AppHandle, path: String) -> Result<(), String> {
+    let mut repos = get_recent_repositories(app.clone())?;
+    repos.retain(|r| r.path != path);
+
+    let name = path
+        .split(['/', '\\'])
+        .next_back()
+        .unwrap_or(&path)
+        .to_string();
+    let last_opened = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    repos.insert(
+        0,
+        RecentRepository {
+            path,
+            name,
+            last_opened,
+        },
+    );
+    repos.truncate(MAX_RECENT_REPOSITORIES);
+
+    let file = recent_repositories_file(&app)?;
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(&repos).map_err(|e| e.to_string())?;
+    std::fs::write(&file, content).map_err(|e| e.to_string())
+}
+
+fn repo_window_title(repo_path: &str) -> String {
     let repo_name = repo_path
         .split(['/', '\\'])
         .next_back()
-        .unwrap_or(&repo_path);
+        .unwrap_or(repo_path);
 
     // Get current branch name
-    let branch_name = match git_ops::open_repository(&repo_path) {
+    let branch_name = match git_ops::open_repository(repo_path) {
         Ok(repo) => {
             if repo.head_detached().unwrap_or(false) {
                 "HEAD (detached)".to_string()
@@ -252,15 +1661,7 @@ fn open_repo_window(app: tauri::AppHandle, repo_path: String) -> Result<(), Stri
         Err(_) => "unknown".to_string(),
     };
 
-    let title = format!("GitX-Tauri - {} [{}]", repo_name, branch_name);
-
-    WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
-        .title(&title)
-        .inner_size(1200.0, 800.0)
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    Ok(())
+    format!("GitX-Tauri - {} [{}]", repo_name, branch_name)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -270,31 +1671,157 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             clone_repository,
+            clone_repository_with_progress,
+            ls_remote,
+            set_ssh_credentials,
             is_git_repository,
+            init_repository,
             open_repository,
+            get_repository_info,
+            get_head_info,
+            get_repository_state,
+            get_repository_size,
+            run_gc,
+            count_objects,
+            get_pack_info,
+            verify_repository,
+            get_archive,
             open_repo_window,
+            get_recent_repositories,
+            add_recent_repository,
             get_branches,
+            get_ahead_behind,
+            get_tracking_branch,
+            set_tracking_branch,
+            unset_tracking_branch,
+            get_merge_base,
+            get_merge_base_many,
+            create_branch,
+            delete_branch,
+            rename_branch,
             get_remotes,
+            add_remote,
+            remove_remote,
+            rename_remote,
+            set_remote_url,
             get_remote_branches,
+            get_remote_refs_cached,
+            update_ref,
+            read_symbolic_ref,
+            set_symbolic_ref,
+            branch_contains_commit,
+            get_branches_containing,
             get_tags,
             get_tag_commit,
+            get_tags_containing,
+            get_annotated_tag_info,
+            describe_commit,
+            get_notes,
+            add_note,
+            remove_note,
+            create_tag,
+            delete_tag,
             get_submodules,
+            get_submodule_status,
+            add_submodule,
+            init_submodule,
+            update_submodule,
+            deinit_submodule,
+            get_worktrees,
+            add_worktree,
+            remove_worktree,
+            get_config,
+            set_config,
+            get_config_entries,
             watch_repo,
+            stop_watching,
             get_status,
             get_diff,
+            get_diff_cached,
+            get_diff_with_rename,
+            get_diff_structured,
+            get_diff_stat,
+            get_index_entries,
+            get_diff_with_options,
             stage_file,
+            bulk_stage_files,
+            bulk_unstage_files,
+            stage_all,
+            unstage_all,
+            discard_all,
             unstage_file,
             stage_hunk,
+            stage_lines,
+            discard_lines,
             unstage_hunk,
             discard_file,
             discard_hunk,
             ignore_file,
+            get_ignored_files,
+            check_ignore,
+            add_gitignore_pattern,
+            remove_gitignore_pattern,
+            get_gitattributes,
+            check_attribute,
+            commit_changes,
+            amend_commit,
+            squash_commits,
+            reset_to_commit,
             checkout_branch,
             fetch_remote,
+            fetch_all_remotes,
+            fetch_multiple_repos,
+            prune_remote,
             pull_remote,
+            push_remote,
+            push_tag,
+            delete_remote_tag,
+            merge_branch,
+            is_merge_in_progress,
+            get_merge_message,
+            abort_merge,
+            get_conflict_files,
+            get_conflict_versions,
+            mark_conflict_resolved,
+            is_rebase_in_progress,
+            rebase_branch,
+            continue_rebase,
+            skip_rebase_step,
+            abort_rebase,
+            cherry_pick_commit,
+            cherry_pick_range,
+            abort_cherry_pick,
+            revert_commit,
+            create_stash,
+            list_stashes,
+            apply_stash,
+            pop_stash,
+            drop_stash,
+            get_stash_diff,
+            get_reflog,
             get_commits,
+            get_commits_between,
+            compare_branches,
+            get_graph_only,
+            get_commits_by_author,
+            get_commits_by_date_range,
+            get_file_history,
+            get_commits_touching_file,
+            search_commits,
+            git_grep,
+            get_file_at_commit,
+            checkout_file,
+            get_tree_entries,
+            get_blame,
+            get_blame_range,
             get_branch_head,
-            get_commit_diff
+            get_commit_diff,
+            get_patch_id,
+            format_patch,
+            apply_patch,
+            get_log_stats,
+            get_commit_diff_with_parent,
+            get_diff_between_commits
         ])
         .run(tauri::generate_context!())
         .expect("error while running GitX-Tauri");
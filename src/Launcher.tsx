@@ -1,8 +1,18 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
+import { errorMessage } from "./errors";
 import "./Launcher.css";
 
+interface CloneProgress {
+  received_objects: number;
+  total_objects: number;
+  received_bytes: number;
+  indexed_deltas: number;
+  total_deltas: number;
+}
+
 interface RecentRepo {
   path: string;
   name: string;
@@ -14,6 +24,9 @@ function Launcher() {
   const [cloneUrl, setCloneUrl] = useState("");
   const [clonePath, setClonePath] = useState("");
   const [isCloning, setIsCloning] = useState(false);
+  const [cloneProgress, setCloneProgress] = useState<CloneProgress | null>(
+    null
+  );
 
   useEffect(() => {
     loadRecentRepos();
@@ -98,12 +111,18 @@ function Launcher() {
     if (!cloneUrl || !clonePath) return;
 
     setIsCloning(true);
+    const unlisten = await listen<CloneProgress>("clone-progress", (event) => {
+      setCloneProgress(event.payload);
+    });
     try {
       // Call Rust function to clone the repository
       const repoName = cloneUrl.split("/").pop()?.replace(".git", "") || "repo";
       const fullPath = `${clonePath}/${repoName}`;
 
-      await invoke("clone_repository", { url: cloneUrl, path: fullPath });
+      await invoke("clone_repository_with_progress", {
+        url: cloneUrl,
+        path: fullPath,
+      });
 
       addToRecentRepos(fullPath);
       await openRepoWindow(fullPath);
@@ -113,12 +132,20 @@ function Launcher() {
       setClonePath("");
     } catch (error) {
       console.error("Failed to clone repository:", error);
-      alert(`Failed to clone repository: ${error}`);
+      alert(`Failed to clone repository: ${errorMessage(error)}`);
     } finally {
+      unlisten();
       setIsCloning(false);
+      setCloneProgress(null);
     }
   };
 
+  const clonePercent = cloneProgress?.total_objects
+    ? Math.floor(
+        (cloneProgress.received_objects / cloneProgress.total_objects) * 100
+      )
+    : 0;
+
   const selectClonePath = async () => {
     try {
       const selected = await open({
@@ -210,7 +237,11 @@ function Launcher() {
                 disabled={!cloneUrl || !clonePath || isCloning}
                 className="action-button"
               >
-                {isCloning ? "Cloning..." : "Clone Repository"}
+                {isCloning
+                  ? cloneProgress
+                    ? `Cloning... ${clonePercent}%`
+                    : "Cloning..."
+                  : "Clone Repository"}
               </button>
             </div>
           </div>
@@ -6,6 +6,7 @@ import { Panel, PanelGroup, PanelResizeHandle } from "react-resizable-panels";
 import { BranchTree } from "./components/BranchTree";
 import { StageView } from "./components/StageView";
 import { CommitHistory, GitCommit } from "./components/CommitHistory";
+import { errorMessage } from "./errors";
 import "./RepoView.css";
 
 interface RepoViewProps {
@@ -20,6 +21,7 @@ interface GitBranch {
 interface GitRemote {
   name: string;
   url: string;
+  push_url?: string;
   branches?: string[];
 }
 
@@ -36,6 +38,9 @@ interface CommitFile {
   additions: number;
   deletions: number;
   lines: DiffLine[];
+  is_binary: boolean;
+  binary_size_old?: number;
+  binary_size_new?: number;
 }
 
 interface DiffLine {
@@ -82,8 +87,10 @@ function RepoView({ repoPath }: RepoViewProps) {
     loadRemotes();
     loadSubmodules();
 
-    // Start watching the repo
-    invoke("watch_repo", { repoPath }).catch((error) =>
+    // Start watching the repo; cleanup passes the id back so it can't stop a
+    // newer watcher on the same path
+    const watcherId = invoke<number>("watch_repo", { repoPath });
+    watcherId.catch((error) =>
       console.error("Failed to start watching repo:", error)
     );
 
@@ -96,6 +103,9 @@ function RepoView({ repoPath }: RepoViewProps) {
 
     return () => {
       unlisten.then((fn) => fn());
+      watcherId
+        .then((id) => invoke("stop_watching", { path: repoPath, id }))
+        .catch(() => {});
     };
   }, [repoPath]);
 
@@ -170,7 +180,7 @@ function RepoView({ repoPath }: RepoViewProps) {
       await invoke("checkout_branch", { path: repoPath, branchName });
       await loadBranches();
     } catch (error) {
-      await message(`Failed to checkout branch: ${error}`, {
+      await message(`Failed to checkout branch: ${errorMessage(error)}`, {
         title: "Checkout Error",
         kind: "error",
       });
@@ -200,7 +210,7 @@ function RepoView({ repoPath }: RepoViewProps) {
       showStatus(`Fetch from ${remote} complete`);
     } catch (error) {
       showStatus("");
-      await message(`Failed to fetch from ${remote}: ${error}`, {
+      await message(`Failed to fetch from ${remote}: ${errorMessage(error)}`, {
         title: "Fetch Error",
         kind: "error",
       });
@@ -216,7 +226,7 @@ function RepoView({ repoPath }: RepoViewProps) {
       showStatus(`Pull from ${remote} complete`);
     } catch (error) {
       showStatus("");
-      await message(`Failed to pull from ${remote}: ${error}`, {
+      await message(`Failed to pull from ${remote}: ${errorMessage(error)}`, {
         title: "Pull Error",
         kind: "error",
       });
@@ -690,6 +700,15 @@ function RepoView({ repoPath }: RepoViewProps) {
                                   </div>
                                   {!collapsedFiles.has(idx) && (
                                     <div className="file-diff">
+                                      {file.is_binary && (
+                                        <div className="diff-line">
+                                          <span className="line-content">
+                                            Binary file not shown (
+                                            {file.binary_size_old ?? 0} →{" "}
+                                            {file.binary_size_new ?? 0} bytes)
+                                          </span>
+                                        </div>
+                                      )}
                                       {file.lines.map((line, i) => {
                                         let className = "diff-line";
                                         if (line.origin === "+") {
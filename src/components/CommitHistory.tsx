@@ -21,6 +21,11 @@ interface GitCommit {
   lines: GraphLine[];
 }
 
+interface CommitPage {
+  commits: GitCommit[];
+  has_more: boolean;
+}
+
 interface GraphLine {
   upper: boolean;
   from: number;
@@ -43,6 +48,12 @@ export function CommitHistory({
   const [graphWidth, setGraphWidth] = useState(60);
   const [messageWidth, setMessageWidth] = useState(400);
   const [branchFilter, setBranchFilter] = useState<string>("All");
+  const [hasMore, setHasMore] = useState({
+    All: false,
+    Local: false,
+    Current: false,
+  });
+  const [loadingMore, setLoadingMore] = useState(false);
 
   useEffect(() => {
     loadAllCommits();
@@ -102,28 +113,31 @@ export function CommitHistory({
     try {
       setLoading(true);
       // Load all commits, local commits, and current branch commits
-      const [allList, localList, currentList] = await Promise.all([
-        invoke<GitCommit[]>("get_commits", {
+      const [allPage, localPage, currentPage] = await Promise.all([
+        invoke<CommitPage>("get_commits", {
           path: repoPath,
           limit: 10000,
           localOnly: false,
           branchName: undefined,
         }),
-        invoke<GitCommit[]>("get_commits", {
+        invoke<CommitPage>("get_commits", {
           path: repoPath,
           limit: 10000,
           localOnly: true,
           branchName: undefined,
         }),
         currentBranch
-          ? invoke<GitCommit[]>("get_commits", {
+          ? invoke<CommitPage>("get_commits", {
               path: repoPath,
               limit: 10000,
               localOnly: false,
               branchName: currentBranch,
             })
-          : Promise.resolve([]),
+          : Promise.resolve({ commits: [], has_more: false }),
       ]);
+      const allList = allPage.commits;
+      const localList = localPage.commits;
+      const currentList = currentPage.commits;
 
       // Store all variants
       setCommits({
@@ -131,6 +145,11 @@ export function CommitHistory({
         Local: localList,
         Current: currentList,
       });
+      setHasMore({
+        All: allPage.has_more,
+        Local: localPage.has_more,
+        Current: currentPage.has_more,
+      });
 
       // Get branch HEAD commit and select it
       if (allList.length > 0 && currentBranch) {
@@ -166,6 +185,33 @@ export function CommitHistory({
     }
   };
 
+  const loadMoreCommits = async () => {
+    const filter = branchFilter as keyof typeof commits;
+    const loaded = commits[filter];
+    if (loaded.length === 0) return;
+
+    try {
+      setLoadingMore(true);
+      // Continue the walk after the last commit we already have
+      const page = await invoke<CommitPage>("get_commits", {
+        path: repoPath,
+        limit: 10000,
+        localOnly: filter === "Local",
+        branchName: filter === "Current" ? currentBranch : undefined,
+        after: loaded[loaded.length - 1].id,
+      });
+      setCommits((prev) => ({
+        ...prev,
+        [filter]: [...prev[filter], ...page.commits],
+      }));
+      setHasMore((prev) => ({ ...prev, [filter]: page.has_more }));
+    } catch (error) {
+      console.error("Failed to load more commits:", error);
+    } finally {
+      setLoadingMore(false);
+    }
+  };
+
   const handleCommitClick = (commit: GitCommit) => {
     setSelectedCommit(commit);
     onCommitSelect(commit);
@@ -314,6 +360,19 @@ export function CommitHistory({
                 );
               })
             )}
+            {!loading && hasMore[branchFilter as keyof typeof hasMore] && (
+              <tr>
+                <td colSpan={5} className="load-more">
+                  <button
+                    className="filter-btn"
+                    onClick={loadMoreCommits}
+                    disabled={loadingMore}
+                  >
+                    {loadingMore ? "Loading..." : "Load more"}
+                  </button>
+                </td>
+              </tr>
+            )}
           </tbody>
         </table>
       </div>
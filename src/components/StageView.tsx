@@ -3,12 +3,15 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { message } from "@tauri-apps/plugin-dialog";
 import { DiffView } from "./DiffView";
+import { errorMessage } from "../errors";
 import "./StageView.css";
 
 interface FileStatus {
   path: string;
+  old_path?: string | null;
   status: string;
   staged: boolean;
+  conflict_type?: string | null;
 }
 
 interface StageViewProps {
@@ -138,7 +141,7 @@ export function StageView({ repoPath }: StageViewProps) {
       setContextMenu(null);
       loadStatus();
     } catch (error) {
-      await message(`Failed to stage file: ${error}`, {
+      await message(`Failed to stage file: ${errorMessage(error)}`, {
         title: "Error",
         kind: "error",
       });
@@ -172,7 +175,7 @@ export function StageView({ repoPath }: StageViewProps) {
       loadDiff(selectedFile, selectedStaged);
     } catch (error) {
       await message(
-        `Failed to ${selectedStaged ? "unstage" : "stage"} hunk: ${error}`,
+        `Failed to ${selectedStaged ? "unstage" : "stage"} hunk: ${errorMessage(error)}`,
         {
           title: "Error",
           kind: "error",
@@ -187,7 +190,7 @@ export function StageView({ repoPath }: StageViewProps) {
       setContextMenu(null);
       loadStatus();
     } catch (error) {
-      await message(`Failed to unstage file: ${error}`, {
+      await message(`Failed to unstage file: ${errorMessage(error)}`, {
         title: "Error",
         kind: "error",
       });
@@ -200,7 +203,7 @@ export function StageView({ repoPath }: StageViewProps) {
       setContextMenu(null);
       loadStatus();
     } catch (error) {
-      await message(`Failed to ignore file: ${error}`, {
+      await message(`Failed to ignore file: ${errorMessage(error)}`, {
         title: "Error",
         kind: "error",
       });
@@ -213,7 +216,7 @@ export function StageView({ repoPath }: StageViewProps) {
       setContextMenu(null);
       loadStatus();
     } catch (error) {
-      await message(`Failed to discard changes: ${error}`, {
+      await message(`Failed to discard changes: ${errorMessage(error)}`, {
         title: "Error",
         kind: "error",
       });
@@ -234,7 +237,7 @@ export function StageView({ repoPath }: StageViewProps) {
       loadStatus();
       loadDiff(selectedFile, selectedStaged);
     } catch (error) {
-      await message(`Failed to discard hunk: ${error}`, {
+      await message(`Failed to discard hunk: ${errorMessage(error)}`, {
         title: "Error",
         kind: "error",
       });
@@ -246,6 +249,7 @@ export function StageView({ repoPath }: StageViewProps) {
     if (s === "M") return "#4ade80";
     if (s === "A") return "#60a5fa";
     if (s === "D") return "#f87171";
+    if (s === "C") return "#fbbf24";
     return "#9ca3af";
   };
 
@@ -290,7 +294,9 @@ export function StageView({ repoPath }: StageViewProps) {
                   {file.status[0].toUpperCase()}
                 </span>
                 <span style={{ color: getStatusColor(file.status) }}>
-                  {file.path}
+                  {file.old_path
+                    ? `${file.old_path} → ${file.path}`
+                    : file.path}
                 </span>
               </div>
             ))}
@@ -342,7 +348,9 @@ export function StageView({ repoPath }: StageViewProps) {
                   {file.status[0].toUpperCase()}
                 </span>
                 <span style={{ color: getStatusColor(file.status) }}>
-                  {file.path}
+                  {file.old_path
+                    ? `${file.old_path} → ${file.path}`
+                    : file.path}
                 </span>
               </div>
             ))}
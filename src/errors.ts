@@ -0,0 +1,20 @@
+export interface GitError {
+  code:
+    | "not_a_repository"
+    | "branch_not_found"
+    | "merge_conflict"
+    | "push_rejected"
+    | "auth_failed"
+    | "network_error"
+    | "permission_denied"
+    | "unknown";
+  message: string;
+}
+
+// Git commands reject with a GitError; anything else is shown as-is
+export function errorMessage(error: unknown): string {
+  if (typeof error === "object" && error !== null && "message" in error) {
+    return String((error as GitError).message);
+  }
+  return String(error);
+}